@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::LogFormat;
+
+/// Columns of the measurement log, in order
+const COLUMNS: [&str; 10] = [
+    "receive-time",
+    "timestamp",
+    "latency",
+    "sequence",
+    "stream-id",
+    "pts",
+    "running-time",
+    "width",
+    "height",
+    "decode-time",
+];
+
+/// One decoded frame, all times in nanoseconds
+#[derive(Debug, Clone, Copy)]
+pub struct Measurement {
+    /// Time at which the frame was received, in the time source of the sink
+    pub receive_time: gst::ClockTime,
+    /// Timestamp decoded from the frame
+    pub timestamp: gst::ClockTime,
+    pub latency: i64,
+    pub sequence: Option<u64>,
+    pub stream_id: Option<u32>,
+    pub pts: Option<gst::ClockTime>,
+    pub running_time: Option<gst::ClockTime>,
+    pub width: u32,
+    pub height: u32,
+    /// Time spent looking for and decoding the code
+    pub decode_time: gst::ClockTime,
+}
+
+impl Measurement {
+    /// Values in the order of [`COLUMNS`], `None` for unknown ones
+    fn values(&self) -> [Option<String>; COLUMNS.len()] {
+        [
+            Some(self.receive_time.nseconds().to_string()),
+            Some(self.timestamp.nseconds().to_string()),
+            Some(self.latency.to_string()),
+            self.sequence.map(|sequence| sequence.to_string()),
+            self.stream_id.map(|stream_id| stream_id.to_string()),
+            self.pts.map(|pts| pts.nseconds().to_string()),
+            self.running_time
+                .map(|running_time| running_time.nseconds().to_string()),
+            Some(self.width.to_string()),
+            Some(self.height.to_string()),
+            Some(self.decode_time.nseconds().to_string()),
+        ]
+    }
+}
+
+/// Buffered writer of measurements, one line per decoded frame
+pub struct MeasurementLog {
+    writer: BufWriter<File>,
+    format: LogFormat,
+}
+
+impl MeasurementLog {
+    /// Creates or truncates the file at `path`, writing the header if the format has one
+    pub fn create(path: &Path, format: LogFormat) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        if format == LogFormat::Csv {
+            writeln!(writer, "{}", COLUMNS.join(","))?;
+        }
+
+        Ok(MeasurementLog { writer, format })
+    }
+
+    pub fn write(&mut self, measurement: &Measurement) -> io::Result<()> {
+        let values = measurement.values();
+
+        match self.format {
+            LogFormat::Csv => {
+                let row = values
+                    .iter()
+                    .map(|value| value.as_deref().unwrap_or_default())
+                    .collect::<Vec<_>>();
+                writeln!(self.writer, "{}", row.join(","))
+            }
+            LogFormat::Jsonl => {
+                // Every value is a number, so there is nothing to escape
+                let fields = COLUMNS
+                    .iter()
+                    .zip(&values)
+                    .map(|(column, value)| {
+                        format!("\"{column}\":{}", value.as_deref().unwrap_or("null"))
+                    })
+                    .collect::<Vec<_>>();
+                writeln!(self.writer, "{{{}}}", fields.join(","))
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,1066 @@
+use gst::glib;
+use gst::prelude::*;
+use gst_video::{VideoFrameExt, VideoFrameRef};
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::codec::{self, DecodeFailure, Decoded, EccLevel, Rect};
+use crate::luma;
+use crate::stats::LatencyStats;
+use crate::timecode;
+use crate::timesource::TimeSource;
+
+mod log;
+mod pacing;
+mod sequence;
+
+use log::{Measurement, MeasurementLog};
+use pacing::Pacing;
+use sequence::{Arrival, Sequences};
+
+/// Format of the measurement log written to `location`
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstQRTimeStampSinkLogFormat")]
+pub enum LogFormat {
+    #[default]
+    #[enum_value(name = "CSV: Comma separated values with a header line", nick = "csv")]
+    Csv = 0,
+    #[enum_value(name = "JSON Lines: One JSON object per line", nick = "jsonl")]
+    Jsonl = 1,
+}
+
+const DEFAULT_TIME_SOURCE: TimeSource = TimeSource::Realtime;
+const DEFAULT_CODE_TYPE: &str = codec::AUTO;
+const DEFAULT_FUTURE_TOLERANCE: gst::ClockTime = gst::ClockTime::SECOND;
+const DEFAULT_MAX_PLAUSIBLE_LATENCY: gst::ClockTime = gst::ClockTime::from_seconds(10);
+const DEFAULT_STATS_WARMUP: u32 = 0;
+const DEFAULT_REPORT_INTERVAL: gst::ClockTime = gst::ClockTime::ZERO;
+const DEFAULT_LOG_FORMAT: LogFormat = LogFormat::Csv;
+const DEFAULT_STUTTER_THRESHOLD: f64 = 1.5;
+/// Number of latencies used to detect unsynchronised clocks
+const CLOCK_CHECK_WINDOW: usize = 100;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    time_source: TimeSource,
+    /// Name of the backend whose codes are looked for, or all of them with `auto`
+    code_type: &'static str,
+    future_tolerance: gst::ClockTime,
+    max_plausible_latency: gst::ClockTime,
+    stats_warmup: u32,
+    report_interval: gst::ClockTime,
+    /// File where every measurement is written, if any
+    location: Option<PathBuf>,
+    log_format: LogFormat,
+    /// Arrival intervals longer than this many frame periods are stutters
+    stutter_threshold: f64,
+    /// Caps of the reference timestamp metas compared with the decoded timestamps, if any
+    reference_timestamp_caps: Option<gst::Caps>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            time_source: DEFAULT_TIME_SOURCE,
+            code_type: DEFAULT_CODE_TYPE,
+            future_tolerance: DEFAULT_FUTURE_TOLERANCE,
+            max_plausible_latency: DEFAULT_MAX_PLAUSIBLE_LATENCY,
+            stats_warmup: DEFAULT_STATS_WARMUP,
+            report_interval: DEFAULT_REPORT_INTERVAL,
+            location: None,
+            log_format: DEFAULT_LOG_FORMAT,
+            stutter_threshold: DEFAULT_STUTTER_THRESHOLD,
+            reference_timestamp_caps: None,
+        }
+    }
+}
+
+/// Frame counters, exposed through the `stats` property
+#[derive(Debug, Default)]
+struct Counters {
+    /// Frames received
+    frames: u64,
+    /// Frames with a timestamp that could be measured
+    decoded: u64,
+    no_grid: u64,
+    grid_decode: u64,
+    unparseable: u64,
+    foreign_magic: u64,
+    future_timestamp: u64,
+    /// Frames missing from the sequence of decoded timestamps, of every stream
+    frames_lost: u64,
+    frames_duplicated: u64,
+    frames_reordered: u64,
+    /// Decoded frames whose reference timestamp meta has the decoded timestamp
+    reference_timestamp_matched: u64,
+    /// Decoded frames whose reference timestamp meta has another timestamp
+    reference_timestamp_mismatched: u64,
+    /// Decoded frames without a reference timestamp meta
+    reference_timestamp_missing: u64,
+    /// Decoded frames whose timecode meta is the timecode of the decoded timestamp
+    timecode_matched: u64,
+    /// Decoded frames whose timecode meta is another timecode
+    timecode_mismatched: u64,
+    /// Error correction level of the last decoded qrcode
+    ecc_level: Option<EccLevel>,
+    /// Version of the last decoded qrcode
+    qr_version: Option<u32>,
+    /// Kind of the last decoded code
+    code_type: Option<&'static str>,
+}
+
+impl Counters {
+    fn add_failure(&mut self, failure: DecodeFailure) {
+        let counter = match failure {
+            DecodeFailure::NoGrid => &mut self.no_grid,
+            DecodeFailure::GridDecode => &mut self.grid_decode,
+            DecodeFailure::Unparseable => &mut self.unparseable,
+            DecodeFailure::ForeignMagic => &mut self.foreign_magic,
+            DecodeFailure::FutureTimestamp => &mut self.future_timestamp,
+        };
+        *counter += 1;
+    }
+
+    fn to_structure(&self) -> gst::Structure {
+        gst::Structure::builder("application/x-qrtimestamp-stats")
+            .field("frames", self.frames)
+            .field("decoded", self.decoded)
+            .field("no-grid", self.no_grid)
+            .field("grid-decode", self.grid_decode)
+            .field("unparseable", self.unparseable)
+            .field("foreign-magic", self.foreign_magic)
+            .field("future-timestamp", self.future_timestamp)
+            .field("frames-lost", self.frames_lost)
+            .field("frames-duplicated", self.frames_duplicated)
+            .field("frames-reordered", self.frames_reordered)
+            .field(
+                "reference-timestamp-matched",
+                self.reference_timestamp_matched,
+            )
+            .field(
+                "reference-timestamp-mismatched",
+                self.reference_timestamp_mismatched,
+            )
+            .field(
+                "reference-timestamp-missing",
+                self.reference_timestamp_missing,
+            )
+            .field("timecode-matched", self.timecode_matched)
+            .field("timecode-mismatched", self.timecode_mismatched)
+            .field_if_some("ecc-level", self.ecc_level)
+            .field_if_some("qr-version", self.qr_version)
+            .field_if_some("code-type", self.code_type)
+            .build()
+    }
+}
+
+/// Measurements of the current `report-interval`, posted as a `qrtimestamp-report` message
+#[derive(Debug, Default)]
+struct Report {
+    /// Time at which the interval started, in the time source of the sink
+    start: Option<gst::ClockTime>,
+    frames: u64,
+    decoded: u64,
+    decode_failures: u64,
+    frames_lost: u64,
+    latency_stats: LatencyStats,
+    pacing: Pacing,
+}
+
+impl Report {
+    fn to_structure(
+        &self,
+        duration: gst::ClockTime,
+        period: Option<gst::ClockTime>,
+    ) -> gst::Structure {
+        let mut structure = gst::Structure::builder("qrtimestamp-report")
+            .field("duration", duration.nseconds())
+            .field("frames", self.frames)
+            .field("decoded", self.decoded)
+            .field("decode-failures", self.decode_failures)
+            .field("frames-lost", self.frames_lost)
+            .build();
+        self.latency_stats.write(&mut structure);
+        self.pacing.write(&mut structure, period);
+
+        structure
+    }
+}
+
+/// Detects source and sink clocks that are not synchronised, from a window of latencies
+#[derive(Debug, Default)]
+struct ClockCheck {
+    latencies: VecDeque<i64>,
+    /// Whether the clocks are currently considered unsynchronised
+    unsynchronised: bool,
+}
+
+impl ClockCheck {
+    /// Adds a latency in nanoseconds, returning the estimated clock offset when the clocks
+    /// become unsynchronised: most latencies in the window are negative or above `max_latency`
+    fn push(&mut self, latency: i64, max_latency: i64) -> Option<i64> {
+        if self.latencies.len() == CLOCK_CHECK_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+
+        if self.latencies.len() < CLOCK_CHECK_WINDOW {
+            return None;
+        }
+
+        let implausible = self
+            .latencies
+            .iter()
+            .filter(|latency| **latency < 0 || **latency > max_latency)
+            .count();
+
+        if !self.unsynchronised && implausible * 10 >= CLOCK_CHECK_WINDOW * 9 {
+            self.unsynchronised = true;
+            return self.offset();
+        }
+
+        if self.unsynchronised && implausible * 2 < CLOCK_CHECK_WINDOW {
+            self.unsynchronised = false;
+        }
+
+        None
+    }
+
+    /// Estimated offset of the sink clock relative to the source clock, in nanoseconds.
+    /// The real latency is never negative, so the smallest measured latency is the best estimate.
+    fn offset(&self) -> Option<i64> {
+        self.unsynchronised
+            .then(|| self.latencies.iter().min().copied())
+            .flatten()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    info: Option<gst_video::VideoInfo>,
+    counters: Counters,
+    clock_check: ClockCheck,
+    /// Latencies measured after the warm-up
+    latency_stats: LatencyStats,
+    /// Latencies measured since start, including the warm-up
+    measurements: u64,
+    pacing: Pacing,
+    sequences: Sequences,
+    report: Report,
+    log: Option<MeasurementLog>,
+}
+
+/// Timestamp decoding and measurement shared by the elements that read qrcodes:
+/// settings, statistics, signals and messages.
+/// The element forwards its properties, signals and stream events to it.
+pub struct Analyzer {
+    cat: gst::DebugCategory,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl Analyzer {
+    /// Creates an analyzer logging to the debug category of the element
+    pub fn new(cat: gst::DebugCategory) -> Self {
+        Analyzer {
+            cat,
+            settings: Mutex::default(),
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn properties() -> Vec<glib::ParamSpec> {
+        vec![
+            glib::ParamSpecEnum::builder_with_default("time-source", DEFAULT_TIME_SOURCE)
+                .nick("Time Source")
+                .blurb("Clock used to read the reception time, it should match the one used by qrtimestampsrc")
+                .mutable_ready()
+                .build(),
+            glib::ParamSpecString::builder("code-type")
+                .nick("Code Type")
+                .blurb("Kind of code carrying the timestamp: qr, watermark, strip or a backend registered by the application. auto tries all of them until one is found, strips, qrcodes and watermarks first")
+                .default_value(Some(DEFAULT_CODE_TYPE))
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt64::builder("future-tolerance")
+                .nick("Future Tolerance")
+                .blurb("How far ahead of the current time, in nanoseconds, a decoded timestamp can be before it is considered invalid")
+                .maximum(gst::ClockTime::MAX.nseconds())
+                .default_value(DEFAULT_FUTURE_TOLERANCE.nseconds())
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt64::builder("max-plausible-latency")
+                .nick("Max Plausible Latency")
+                .blurb("Latencies above this value, in nanoseconds, are considered a sign of unsynchronised clocks, like negative ones")
+                .maximum(i64::MAX as u64)
+                .default_value(DEFAULT_MAX_PLAUSIBLE_LATENCY.nseconds())
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt::builder("stats-warmup")
+                .nick("Statistics Warm-up")
+                .blurb("Number of initial measurements excluded from the latency statistics")
+                .default_value(DEFAULT_STATS_WARMUP)
+                .mutable_ready()
+                .build(),
+            glib::ParamSpecUInt64::builder("report-interval")
+                .nick("Report Interval")
+                .blurb("Interval, in nanoseconds, between qrtimestamp-report element messages with the statistics of the interval (0 = disabled)")
+                .maximum(gst::ClockTime::MAX.nseconds())
+                .default_value(DEFAULT_REPORT_INTERVAL.nseconds())
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecString::builder("location")
+                .nick("Location")
+                .blurb("File where every measurement is written, in the log-format")
+                .mutable_ready()
+                .build(),
+            glib::ParamSpecEnum::builder_with_default("log-format", DEFAULT_LOG_FORMAT)
+                .nick("Log Format")
+                .blurb("Format of the measurements written to location")
+                .mutable_ready()
+                .build(),
+            glib::ParamSpecDouble::builder("stutter-threshold")
+                .nick("Stutter Threshold")
+                .blurb("Intervals between frame arrivals longer than this many frame periods are counted as stutters")
+                .minimum(1.0)
+                .default_value(DEFAULT_STUTTER_THRESHOLD)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecBoxed::builder::<gst::Caps>("reference-timestamp-caps")
+                .nick("Reference Timestamp Caps")
+                .blurb("Compare the GstReferenceTimestampMeta with these caps, e.g. timestamp/x-unix, with the decoded timestamp of every frame, if unset metas are ignored")
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                .nick("Statistics")
+                .blurb("Frame and decode failure counters, and latency statistics in nanoseconds")
+                .read_only()
+                .build(),
+        ]
+    }
+
+    /// Signals of the element, `reset_stats` is the class handler of the `reset-stats` action signal
+    pub fn signals(
+        reset_stats: impl Fn(&[glib::Value]) + Send + Sync + 'static,
+    ) -> Vec<glib::subclass::Signal> {
+        vec![
+            glib::subclass::Signal::builder("on-render")
+                .param_types([gst_video::VideoInfo::static_type(), i64::static_type()])
+                .build(),
+            glib::subclass::Signal::builder("on-decode-failed")
+                .param_types([
+                    gst_video::VideoInfo::static_type(),
+                    DecodeFailure::static_type(),
+                    Option::<String>::static_type(),
+                ])
+                .build(),
+            glib::subclass::Signal::builder("reset-stats")
+                .action()
+                .class_handler(move |_token, args| {
+                    reset_stats(args);
+
+                    None
+                })
+                .build(),
+        ]
+    }
+
+    /// Sets one of the properties of the analyzer, returns `false` if `pspec` is not one of them
+    pub fn set_property(
+        &self,
+        element: &gst::Element,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) -> bool {
+        match pspec.name() {
+            "time-source" => {
+                let mut settings = self.settings.lock().unwrap();
+                let time_source = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing time-source from {:?} to {:?}",
+                    settings.time_source,
+                    time_source,
+                );
+                settings.time_source = time_source;
+            }
+            "code-type" => {
+                let mut settings = self.settings.lock().unwrap();
+                let name = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+                let code_type = match name.as_deref() {
+                    Some(codec::AUTO) => Some(codec::AUTO),
+                    name => name
+                        .and_then(codec::backend)
+                        .map(|(code_type, _)| code_type),
+                };
+                let Some(code_type) = code_type else {
+                    gst::warning!(
+                        self.cat,
+                        obj = element,
+                        "Unknown code-type {name:?}, keeping {}",
+                        settings.code_type,
+                    );
+                    return true;
+                };
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing code-type from {} to {}",
+                    settings.code_type,
+                    code_type,
+                );
+                settings.code_type = code_type;
+            }
+            "future-tolerance" => {
+                let mut settings = self.settings.lock().unwrap();
+                let future_tolerance =
+                    gst::ClockTime::from_nseconds(value.get().expect("type checked upstream"));
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing future-tolerance from {} to {}",
+                    settings.future_tolerance,
+                    future_tolerance,
+                );
+                settings.future_tolerance = future_tolerance;
+            }
+            "max-plausible-latency" => {
+                let mut settings = self.settings.lock().unwrap();
+                let max_plausible_latency =
+                    gst::ClockTime::from_nseconds(value.get().expect("type checked upstream"));
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing max-plausible-latency from {} to {}",
+                    settings.max_plausible_latency,
+                    max_plausible_latency,
+                );
+                settings.max_plausible_latency = max_plausible_latency;
+            }
+            "stats-warmup" => {
+                let mut settings = self.settings.lock().unwrap();
+                let stats_warmup = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing stats-warmup from {} to {}",
+                    settings.stats_warmup,
+                    stats_warmup,
+                );
+                settings.stats_warmup = stats_warmup;
+            }
+            "report-interval" => {
+                let mut settings = self.settings.lock().unwrap();
+                let report_interval =
+                    gst::ClockTime::from_nseconds(value.get().expect("type checked upstream"));
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing report-interval from {} to {}",
+                    settings.report_interval,
+                    report_interval,
+                );
+                settings.report_interval = report_interval;
+            }
+            "location" => {
+                let mut settings = self.settings.lock().unwrap();
+                let location = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .map(PathBuf::from);
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing location from {:?} to {:?}",
+                    settings.location,
+                    location,
+                );
+                settings.location = location;
+            }
+            "log-format" => {
+                let mut settings = self.settings.lock().unwrap();
+                let log_format = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing log-format from {:?} to {:?}",
+                    settings.log_format,
+                    log_format,
+                );
+                settings.log_format = log_format;
+            }
+            "stutter-threshold" => {
+                let mut settings = self.settings.lock().unwrap();
+                let stutter_threshold = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing stutter-threshold from {} to {}",
+                    settings.stutter_threshold,
+                    stutter_threshold,
+                );
+                settings.stutter_threshold = stutter_threshold;
+            }
+            "reference-timestamp-caps" => {
+                let mut settings = self.settings.lock().unwrap();
+                let reference_timestamp_caps = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing reference-timestamp-caps from {:?} to {:?}",
+                    settings.reference_timestamp_caps,
+                    reference_timestamp_caps,
+                );
+                settings.reference_timestamp_caps = reference_timestamp_caps;
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Value of one of the properties of the analyzer, `None` if `pspec` is not one of them
+    pub fn property(&self, pspec: &glib::ParamSpec) -> Option<glib::Value> {
+        Some(match pspec.name() {
+            "time-source" => self.settings.lock().unwrap().time_source.to_value(),
+            "code-type" => self.settings.lock().unwrap().code_type.to_value(),
+            "future-tolerance" => self
+                .settings
+                .lock()
+                .unwrap()
+                .future_tolerance
+                .nseconds()
+                .to_value(),
+            "max-plausible-latency" => self
+                .settings
+                .lock()
+                .unwrap()
+                .max_plausible_latency
+                .nseconds()
+                .to_value(),
+            "stats-warmup" => self.settings.lock().unwrap().stats_warmup.to_value(),
+            "report-interval" => self
+                .settings
+                .lock()
+                .unwrap()
+                .report_interval
+                .nseconds()
+                .to_value(),
+            "location" => self
+                .settings
+                .lock()
+                .unwrap()
+                .location
+                .as_ref()
+                .map(|location| location.to_string_lossy().into_owned())
+                .to_value(),
+            "log-format" => self.settings.lock().unwrap().log_format.to_value(),
+            "stutter-threshold" => self.settings.lock().unwrap().stutter_threshold.to_value(),
+            "reference-timestamp-caps" => self
+                .settings
+                .lock()
+                .unwrap()
+                .reference_timestamp_caps
+                .to_value(),
+            "stats" => self.stats().to_value(),
+            _ => return None,
+        })
+    }
+
+    pub fn info(&self) -> Option<gst_video::VideoInfo> {
+        self.state.lock().unwrap().info.clone()
+    }
+
+    pub fn set_info(&self, info: gst_video::VideoInfo) {
+        self.state.lock().unwrap().info.replace(info);
+    }
+
+    pub fn start(&self, element: &gst::Element) -> Result<(), gst::ErrorMessage> {
+        let settings = self.settings.lock().unwrap().clone();
+        let log = settings
+            .location
+            .map(|location| {
+                MeasurementLog::create(&location, settings.log_format).map_err(|error| {
+                    gst::error_msg!(
+                        gst::ResourceError::OpenWrite,
+                        ["Failed to create {}: {error}", location.display()]
+                    )
+                })
+            })
+            .transpose()?;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.counters = Counters::default();
+            state.clock_check = ClockCheck::default();
+            state.latency_stats = LatencyStats::default();
+            state.measurements = 0;
+            state.pacing = Pacing::default();
+            state.sequences = Sequences::default();
+            state.report = Report::default();
+            state.log = log;
+        }
+
+        gst::debug!(self.cat, obj = element, "Started");
+
+        Ok(())
+    }
+
+    pub fn stop(&self, element: &gst::Element) -> Result<(), gst::ErrorMessage> {
+        if let Some(mut log) = self.state.lock().unwrap().log.take() {
+            log.flush().map_err(|error| {
+                gst::error_msg!(
+                    gst::ResourceError::Write,
+                    ["Failed to flush the measurement log: {error}"]
+                )
+            })?;
+        }
+
+        gst::debug!(self.cat, obj = element, "Stopped");
+
+        Ok(())
+    }
+
+    /// Measures the latency of `buffer`, `segment` gives its running time
+    pub fn render(
+        &self,
+        element: &gst::Element,
+        buffer: &gst::Buffer,
+        segment: &gst::Segment,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        // We need to get time asap to avoid adding the time to the decode logic,
+        // the rest of the settings is cloned afterwards
+        let time_source = self.settings.lock().unwrap().time_source;
+        let Some(time) = time_source.now(element) else {
+            gst::warning!(
+                self.cat,
+                obj = element,
+                "No clock available for time source {:?}",
+                time_source
+            );
+
+            return Ok(gst::FlowSuccess::Ok);
+        };
+        let settings = self.settings.lock().unwrap().clone();
+
+        let result = self.measure(element, buffer, segment, &settings, time);
+
+        if settings.report_interval > gst::ClockTime::ZERO {
+            self.report_if_due(element, settings.report_interval, time);
+        }
+
+        result
+    }
+
+    /// Posts the summary and flushes the measurement log
+    pub fn eos(&self, element: &gst::Element) {
+        self.post_summary(element);
+
+        if let Some(log) = self.state.lock().unwrap().log.as_mut() {
+            if let Err(error) = log.flush() {
+                gst::element_warning!(
+                    element,
+                    gst::ResourceError::Write,
+                    ["Failed to flush the measurement log: {error}"]
+                );
+            }
+        }
+    }
+
+    /// Decodes the timestamp of `buffer` received at `time`, and updates the statistics
+    fn measure(
+        &self,
+        element: &gst::Element,
+        buffer: &gst::Buffer,
+        segment: &gst::Segment,
+        settings: &Settings,
+        time: gst::ClockTime,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let Some(info) = self.state.lock().unwrap().info.clone() else {
+            return Ok(gst::FlowSuccess::Ok);
+        };
+
+        let frame = VideoFrameRef::from_buffer_ref_readable(buffer, &info)
+            .map_err(|_| gst::FlowError::Error)?;
+
+        let crop = buffer.meta::<gst_video::VideoCropMeta>().map(|meta| {
+            let (x, y, width, height) = meta.rect();
+            Rect {
+                x,
+                y,
+                width,
+                height,
+            }
+        });
+
+        let Some(image) = luma::image(&frame, crop) else {
+            gst::error!(
+                self.cat,
+                obj = element,
+                "Unsupported format {:?}",
+                frame.format()
+            );
+
+            return Err(gst::FlowError::NotSupported);
+        };
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.counters.frames += 1;
+            state.report.frames += 1;
+
+            let period = frame_period(&info);
+            let threshold = settings.stutter_threshold;
+            state.pacing.arrival(time, period, threshold);
+            state.report.pacing.arrival(time, period, threshold);
+        }
+
+        let decode_start = Instant::now();
+        let decoded = codec::detect(&image, settings.code_type);
+        let decode_time = gst::ClockTime::from_nseconds(decode_start.elapsed().as_nanos() as u64);
+
+        let (decoded, code_type) = match decoded {
+            Ok((Decoded { payload, .. }, _))
+                if payload.timestamp.saturating_sub(time.nseconds())
+                    > settings.future_tolerance.nseconds() =>
+            {
+                let timestamp = gst::ClockTime::from_nseconds(payload.timestamp);
+                gst::debug!(
+                    self.cat,
+                    obj = element,
+                    "Decoded timestamp {} is {} in the future",
+                    timestamp,
+                    timestamp - time
+                );
+                self.decode_failed(element, &info, DecodeFailure::FutureTimestamp, None);
+
+                return Ok(gst::FlowSuccess::Ok);
+            }
+            Ok(decoded) => decoded,
+            Err((failure, content)) => {
+                self.decode_failed(element, &info, failure, content);
+
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+        gst::trace!(
+            self.cat,
+            obj = element,
+            "Decoded {decoded:?} from {code_type} code"
+        );
+        let payload = decoded.payload;
+
+        // Elements that re-create buffers drop the metas, while the pixels keep the timestamp
+        let reference = settings.reference_timestamp_caps.as_ref().map(|caps| {
+            buffer
+                .iter_meta::<gst::ReferenceTimestampMeta>()
+                .find(|meta| meta.reference().can_intersect(caps))
+                .map(|meta| meta.timestamp())
+        });
+        // The timecode of the decoded timestamp is derived at the framerate of the meta
+        let timecode = buffer
+            .meta::<gst_video::VideoTimeCodeMeta>()
+            .map(|meta| meta.tc())
+            .map(|tc| (timecode::from_timestamp(payload.timestamp, tc.fps()), tc));
+
+        // Negative latencies are reported as they are, they mean that the clocks are not synchronised
+        let latency = time.nseconds() as i64 - payload.timestamp as i64;
+
+        let mut frames_lost = None;
+        let clock_offset = {
+            let mut state = self.state.lock().unwrap();
+            let counters = &mut state.counters;
+            counters.decoded += 1;
+            counters.code_type = Some(code_type);
+            // Only some codes, like qrcodes, have an error correction level and a version
+            if decoded.ecc_level.is_some() || decoded.version.is_some() {
+                counters.ecc_level = decoded.ecc_level;
+                counters.qr_version = decoded.version;
+            }
+            match reference {
+                Some(Some(reference)) if reference.nseconds() == payload.timestamp => {
+                    counters.reference_timestamp_matched += 1
+                }
+                Some(Some(reference)) => {
+                    gst::debug!(
+                        self.cat,
+                        obj = element,
+                        "Reference timestamp {reference} differs from the decoded timestamp {}",
+                        gst::ClockTime::from_nseconds(payload.timestamp)
+                    );
+                    counters.reference_timestamp_mismatched += 1;
+                }
+                Some(None) => counters.reference_timestamp_missing += 1,
+                None => (),
+            }
+            match timecode {
+                Some((Some(expected), tc)) if expected == tc => counters.timecode_matched += 1,
+                Some((expected, tc)) => {
+                    gst::debug!(
+                        self.cat,
+                        obj = element,
+                        "Timecode {tc} differs from the timecode {expected:?} of the decoded timestamp"
+                    );
+                    counters.timecode_mismatched += 1;
+                }
+                None => (),
+            }
+
+            let arrival = payload
+                .sequence
+                .map(|sequence| state.sequences.push(payload.stream_id, sequence));
+            match arrival {
+                Some(Arrival::InOrder { lost }) if lost > 0 => {
+                    state.counters.frames_lost = state.counters.frames_lost.saturating_add(lost);
+                    state.report.frames_lost = state.report.frames_lost.saturating_add(lost);
+                    frames_lost = Some(lost);
+                }
+                Some(Arrival::Duplicate) => state.counters.frames_duplicated += 1,
+                Some(Arrival::Reordered) => {
+                    state.counters.frames_lost = state.counters.frames_lost.saturating_sub(1);
+                    state.report.frames_lost = state.report.frames_lost.saturating_sub(1);
+                    state.counters.frames_reordered += 1;
+                }
+                Some(Arrival::Restart) => {
+                    gst::info!(
+                        self.cat,
+                        obj = element,
+                        "Sequence restarted for stream {:?}",
+                        payload.stream_id
+                    );
+                }
+                _ => (),
+            }
+
+            state.measurements += 1;
+            if state.measurements > settings.stats_warmup as u64 {
+                state.latency_stats.push(latency);
+            }
+
+            state.pacing.latency(latency);
+            let report = &mut state.report;
+            report.decoded += 1;
+            report.latency_stats.push(latency);
+            report.pacing.latency(latency);
+
+            state
+                .clock_check
+                .push(latency, settings.max_plausible_latency.nseconds() as i64)
+        };
+
+        if let Some(offset) = clock_offset {
+            self.post_clock_warning(element, offset);
+        }
+
+        if let (Some(lost), Some(sequence)) = (frames_lost, payload.sequence) {
+            gst::debug!(
+                self.cat,
+                obj = element,
+                "Lost {lost} frames before sequence {sequence}"
+            );
+
+            let structure = gst::Structure::builder("frames-lost")
+                .field_if_some("stream-id", payload.stream_id)
+                .field("lost", lost)
+                .field("first", sequence - lost)
+                .field("sequence", sequence)
+                .build();
+            self.post_element_message(element, structure);
+        }
+
+        if let Some(log) = self.state.lock().unwrap().log.as_mut() {
+            let pts = buffer.pts();
+            let running_time = pts.and_then(|pts| {
+                segment
+                    .downcast_ref::<gst::ClockTime>()
+                    .and_then(|segment| segment.to_running_time(pts))
+            });
+            let measurement = Measurement {
+                receive_time: time,
+                timestamp: gst::ClockTime::from_nseconds(payload.timestamp),
+                latency,
+                sequence: payload.sequence,
+                stream_id: payload.stream_id,
+                pts,
+                running_time,
+                width: info.width(),
+                height: info.height(),
+                decode_time,
+            };
+
+            if let Err(error) = log.write(&measurement) {
+                gst::element_error!(
+                    element,
+                    gst::ResourceError::Write,
+                    ["Failed to write the measurement log: {error}"]
+                );
+
+                return Err(gst::FlowError::Error);
+            }
+        }
+
+        element.emit_by_name::<()>("on-render", &[&info, &latency]);
+
+        gst::debug!(self.cat, obj = element, "Latency: {latency} ns");
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    /// Posts the report of the current interval if it is over at `time`
+    fn report_if_due(
+        &self,
+        element: &gst::Element,
+        interval: gst::ClockTime,
+        time: gst::ClockTime,
+    ) {
+        let structure = {
+            let mut state = self.state.lock().unwrap();
+            let start = *state.report.start.get_or_insert(time);
+            let Some(duration) = time
+                .checked_sub(start)
+                .filter(|elapsed| *elapsed >= interval)
+            else {
+                return;
+            };
+
+            let report = std::mem::take(&mut state.report);
+            state.report.start = Some(time);
+            state.report.pacing = report.pacing.next_window();
+            report.to_structure(duration, state.info.as_ref().and_then(frame_period))
+        };
+
+        self.post_element_message(element, structure);
+    }
+
+    /// Posts the statistics of the whole run, preceded by the report of the unfinished interval
+    fn post_summary(&self, element: &gst::Element) {
+        let settings = self.settings.lock().unwrap().clone();
+        if settings.report_interval > gst::ClockTime::ZERO {
+            let time = settings.time_source.now(element);
+            let report = {
+                let mut state = self.state.lock().unwrap();
+                let report = std::mem::take(&mut state.report);
+                state.report.pacing = report.pacing.next_window();
+                let period = state.info.as_ref().and_then(frame_period);
+                report
+                    .start
+                    .zip(time)
+                    .filter(|_| report.frames > 0)
+                    .map(|(start, time)| report.to_structure(time.saturating_sub(start), period))
+            };
+
+            if let Some(report) = report {
+                self.post_element_message(element, report);
+            }
+        }
+
+        let mut summary = self.stats();
+        summary.set_name("qrtimestamp-summary");
+        self.post_element_message(element, summary);
+    }
+
+    fn post_element_message(&self, element: &gst::Element, structure: gst::Structure) {
+        gst::debug!(self.cat, obj = element, "Posting {structure}");
+
+        let message = gst::message::Element::builder(structure)
+            .src(element)
+            .build();
+
+        let _ = element.post_message(message);
+    }
+
+    pub fn stats(&self) -> gst::Structure {
+        let state = self.state.lock().unwrap();
+
+        let mut stats = state.counters.to_structure();
+        if state.counters.frames > 0 {
+            let success_rate = state.counters.decoded as f64 / state.counters.frames as f64;
+            stats.set("decode-success-rate", success_rate);
+        }
+        stats.set("streams", state.sequences.to_array());
+        state.latency_stats.write(&mut stats);
+        state
+            .pacing
+            .write(&mut stats, state.info.as_ref().and_then(frame_period));
+        stats.set("clock-unsynchronised", state.clock_check.unsynchronised);
+        if let Some(offset) = state.clock_check.offset() {
+            stats.set("clock-offset", offset);
+        }
+
+        stats
+    }
+
+    pub fn reset_stats(&self, element: &gst::Element) {
+        gst::debug!(self.cat, obj = element, "Resetting statistics");
+
+        let mut state = self.state.lock().unwrap();
+        state.counters = Counters::default();
+        state.sequences.reset_counters();
+        state.latency_stats = LatencyStats::default();
+        state.pacing = state.pacing.next_window();
+        state.report = Report {
+            pacing: state.report.pacing.next_window(),
+            ..Default::default()
+        };
+    }
+
+    fn post_clock_warning(&self, element: &gst::Element, offset: i64) {
+        gst::warning!(
+            self.cat,
+            obj = element,
+            "Clocks look unsynchronised, estimated offset: {offset} ns"
+        );
+
+        let details = gst::Structure::builder("qrtimestamp-clock-offset")
+            .field("clock-offset", offset)
+            .build();
+        let message = gst::message::Warning::builder(
+            gst::CoreError::Clock,
+            &format!("Source and sink clocks look unsynchronised, estimated offset: {offset} ns"),
+        )
+        .src(element)
+        .details(details)
+        .build();
+
+        let _ = element.post_message(message);
+    }
+
+    fn decode_failed(
+        &self,
+        element: &gst::Element,
+        info: &gst_video::VideoInfo,
+        failure: DecodeFailure,
+        content: Option<String>,
+    ) {
+        gst::debug!(
+            self.cat,
+            obj = element,
+            "Failed decoding frame: {failure:?} (content: {content:?})"
+        );
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.counters.add_failure(failure);
+            state.report.decode_failures += 1;
+        }
+
+        element.emit_by_name::<()>("on-decode-failed", &[info, &failure, &content]);
+    }
+}
+
+/// Nominal interval between frames, `None` for variable framerates
+fn frame_period(info: &gst_video::VideoInfo) -> Option<gst::ClockTime> {
+    let fps = info.fps();
+    if fps.numer() <= 0 || fps.denom() <= 0 {
+        return None;
+    }
+
+    gst::ClockTime::SECOND.mul_div_floor(fps.denom() as u64, fps.numer() as u64)
+}
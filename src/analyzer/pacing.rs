@@ -0,0 +1,83 @@
+/// Bins of the arrival interval histogram, each half a nominal period wide.
+/// The last bin also counts every longer interval.
+const HISTOGRAM_BINS: usize = 9;
+
+/// Smoothness of the frame arrivals, compared with the nominal frame period
+#[derive(Debug, Default, Clone)]
+pub struct Pacing {
+    last_arrival: Option<gst::ClockTime>,
+    last_latency: Option<i64>,
+    /// Interarrival jitter estimate, in nanoseconds
+    jitter: Option<f64>,
+    intervals: [u64; HISTOGRAM_BINS],
+    interval_max: Option<gst::ClockTime>,
+    /// Intervals longer than the stutter threshold
+    stutters: u64,
+}
+
+impl Pacing {
+    /// Adds a frame that arrived at `time`, `stutter_threshold` being in nominal periods
+    pub fn arrival(
+        &mut self,
+        time: gst::ClockTime,
+        period: Option<gst::ClockTime>,
+        stutter_threshold: f64,
+    ) {
+        let last_arrival = self.last_arrival.replace(time);
+        let Some(interval) = last_arrival.and_then(|last_arrival| time.checked_sub(last_arrival))
+        else {
+            return;
+        };
+
+        self.interval_max = self.interval_max.max(Some(interval));
+
+        let Some(period) = period.filter(|period| !period.is_zero()) else {
+            return;
+        };
+
+        let periods = interval.nseconds() as f64 / period.nseconds() as f64;
+        let bin = ((periods * 2.0) as usize).min(HISTOGRAM_BINS - 1);
+        self.intervals[bin] += 1;
+
+        if periods > stutter_threshold {
+            self.stutters += 1;
+        }
+    }
+
+    /// Adds the latency of a decoded frame, updating the jitter as in RFC 3550, section 6.4.1:
+    /// the latency difference is the difference between the arrival and the sending intervals
+    pub fn latency(&mut self, latency: i64) {
+        let Some(last_latency) = self.last_latency.replace(latency) else {
+            return;
+        };
+
+        let difference = latency.abs_diff(last_latency) as f64;
+        let jitter = self.jitter.unwrap_or_default();
+        self.jitter = Some(jitter + (difference - jitter) / 16.0);
+    }
+
+    /// Pacing of the next window, it continues from this one with cleared counters
+    pub fn next_window(&self) -> Self {
+        Pacing {
+            last_arrival: self.last_arrival,
+            last_latency: self.last_latency,
+            jitter: self.jitter,
+            ..Default::default()
+        }
+    }
+
+    /// Writes the pacing as fields of `structure`, times in nanoseconds
+    pub fn write(&self, structure: &mut gst::StructureRef, period: Option<gst::ClockTime>) {
+        if let Some(jitter) = self.jitter {
+            structure.set("jitter", jitter.round() as u64);
+        }
+        if let Some(period) = period {
+            structure.set("expected-interval", period.nseconds());
+        }
+        if let Some(interval_max) = self.interval_max {
+            structure.set("interval-max", interval_max.nseconds());
+        }
+        structure.set("interval-histogram", gst::Array::new(self.intervals));
+        structure.set("stutters", self.stutters);
+    }
+}
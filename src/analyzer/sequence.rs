@@ -0,0 +1,128 @@
+use gst::prelude::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Missing sequence numbers further behind the highest one are forgotten.
+/// A frame that far behind means that the source restarted rather than a late frame.
+const WINDOW: u64 = 1024;
+
+/// How a frame relates to the previous ones of its stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrival {
+    /// Newer than every previous frame, `lost` frames are missing in between
+    InOrder { lost: u64 },
+    /// Already received
+    Duplicate,
+    /// Counted as lost before, it arrived late
+    Reordered,
+    /// Far behind the previous frames, the source restarted
+    Restart,
+}
+
+/// Frame loss, duplicate and reorder detection for the sequence numbers of a single stream
+#[derive(Debug, Default)]
+struct StreamSequence {
+    highest: Option<u64>,
+    /// Sequence numbers inside the window that were skipped
+    missing: BTreeSet<u64>,
+    /// Unique frames received
+    received: u64,
+    lost: u64,
+    duplicates: u64,
+    reordered: u64,
+}
+
+impl StreamSequence {
+    fn push(&mut self, sequence: u64) -> Arrival {
+        let Some(highest) = self.highest else {
+            self.highest = Some(sequence);
+            self.received += 1;
+            return Arrival::InOrder { lost: 0 };
+        };
+
+        if sequence > highest {
+            let lost = sequence - highest - 1;
+            let oldest = sequence.saturating_sub(WINDOW);
+            self.missing.extend((highest + 1).max(oldest)..sequence);
+            self.missing = self.missing.split_off(&oldest);
+            self.highest = Some(sequence);
+            self.received += 1;
+            self.lost = self.lost.saturating_add(lost);
+
+            return Arrival::InOrder { lost };
+        }
+
+        // Sequences come from the payload, they can be anywhere in the u64 range
+        if highest - sequence > WINDOW {
+            self.highest = Some(sequence);
+            self.missing.clear();
+            self.received += 1;
+
+            return Arrival::Restart;
+        }
+
+        if self.missing.remove(&sequence) {
+            self.received += 1;
+            // The frame may have been counted as lost before the counters were reset
+            self.lost = self.lost.saturating_sub(1);
+            self.reordered += 1;
+
+            return Arrival::Reordered;
+        }
+
+        self.duplicates += 1;
+        Arrival::Duplicate
+    }
+
+    /// Frames lost as a percentage of the frames sent
+    fn loss_percentage(&self) -> f64 {
+        let sent = self.received + self.lost;
+        if sent == 0 {
+            return 0.0;
+        }
+
+        self.lost as f64 * 100.0 / sent as f64
+    }
+
+    fn to_structure(&self, stream_id: Option<u32>) -> gst::Structure {
+        gst::Structure::builder("stream")
+            .field_if_some("stream-id", stream_id)
+            .field("received", self.received)
+            .field("lost", self.lost)
+            .field("duplicates", self.duplicates)
+            .field("reordered", self.reordered)
+            .field("loss-percentage", self.loss_percentage())
+            .build()
+    }
+}
+
+/// Sequence tracking of every stream, identified by the stream id of the payload
+#[derive(Debug, Default)]
+pub struct Sequences {
+    streams: BTreeMap<Option<u32>, StreamSequence>,
+}
+
+impl Sequences {
+    pub fn push(&mut self, stream_id: Option<u32>, sequence: u64) -> Arrival {
+        self.streams.entry(stream_id).or_default().push(sequence)
+    }
+
+    /// Clears the counters, while keeping the sequence numbers needed to detect new losses
+    pub fn reset_counters(&mut self) {
+        for stream in self.streams.values_mut() {
+            stream.received = 0;
+            stream.lost = 0;
+            stream.duplicates = 0;
+            stream.reordered = 0;
+        }
+    }
+
+    /// Counters of every stream, as an array of `stream` structures
+    pub fn to_array(&self) -> gst::Array {
+        gst::Array::from_values(
+            self.streams
+                .iter()
+                .map(|(stream_id, stream)| stream.to_structure(*stream_id).to_send_value()),
+        )
+    }
+}
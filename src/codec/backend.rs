@@ -0,0 +1,168 @@
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use super::{
+    EccLevel, Placement, QrDecoder, QrEncoder, StripDecoder, StripEncoder, TimestampDecoder,
+    TimestampEncoder, WatermarkDecoder, WatermarkEncoder,
+};
+
+/// Code type name that looks for every registered code, it can't be registered
+pub const AUTO: &str = "auto";
+
+/// Drawing settings of the elements, each backend uses the ones that apply to its code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderSettings {
+    pub placement: Placement,
+    pub ecc_level: EccLevel,
+    /// Minimum size of each qrcode module in pixels
+    pub module_size: u32,
+    /// Width of the light border around the qrcode, in modules
+    pub quiet_zone: u32,
+    /// Fixed qrcode version, `0` for the smallest one that fits the payload
+    pub qr_version: u32,
+    /// Luma change of the watermark, in 8 bits levels
+    pub watermark_strength: u32,
+}
+
+impl Default for EncoderSettings {
+    fn default() -> Self {
+        EncoderSettings {
+            placement: Placement::default(),
+            ecc_level: EccLevel::M,
+            module_size: 1,
+            quiet_zone: 4,
+            qr_version: 0,
+            watermark_strength: 4,
+        }
+    }
+}
+
+/// Kind of code carrying the timestamp payload, selected by its name with the `code-type`
+/// property of the elements once it is registered with [`register`]
+pub trait Backend: Sync {
+    /// Encoder drawing codes following `settings`, built again when they change
+    fn encoder(&self, settings: &EncoderSettings) -> Arc<dyn TimestampEncoder>;
+
+    /// Decoder of the codes drawn by the encoders
+    fn decoder(&self) -> &dyn TimestampDecoder;
+}
+
+struct QrBackend;
+
+impl Backend for QrBackend {
+    fn encoder(&self, settings: &EncoderSettings) -> Arc<dyn TimestampEncoder> {
+        Arc::new(QrEncoder {
+            placement: settings.placement,
+            ecc_level: settings.ecc_level,
+            module_size: settings.module_size,
+            quiet_zone: settings.quiet_zone,
+            version: settings.qr_version,
+        })
+    }
+
+    fn decoder(&self) -> &dyn TimestampDecoder {
+        &QrDecoder
+    }
+}
+
+struct WatermarkBackend;
+
+impl Backend for WatermarkBackend {
+    fn encoder(&self, settings: &EncoderSettings) -> Arc<dyn TimestampEncoder> {
+        Arc::new(WatermarkEncoder {
+            strength: settings.watermark_strength,
+        })
+    }
+
+    fn decoder(&self) -> &dyn TimestampDecoder {
+        &WatermarkDecoder
+    }
+}
+
+struct StripBackend;
+
+impl Backend for StripBackend {
+    fn encoder(&self, settings: &EncoderSettings) -> Arc<dyn TimestampEncoder> {
+        Arc::new(StripEncoder {
+            placement: settings.placement,
+        })
+    }
+
+    fn decoder(&self) -> &dyn TimestampDecoder {
+        &StripDecoder
+    }
+}
+
+/// Backends of the crate, the cheapest to detect first
+const BUILT_IN: [(&str, &dyn Backend); 3] = [
+    ("strip", &StripBackend),
+    ("qr", &QrBackend),
+    ("watermark", &WatermarkBackend),
+];
+
+/// Backends registered by the application, looked for after the built-in ones
+static REGISTERED: RwLock<Vec<(&'static str, &'static dyn Backend)>> = RwLock::new(Vec::new());
+
+/// Error of [`register`], the name is already taken by another backend or by [`AUTO`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlreadyRegistered(pub &'static str);
+
+impl fmt::Display for AlreadyRegistered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Code type {} is already registered", self.0)
+    }
+}
+
+impl std::error::Error for AlreadyRegistered {}
+
+/// Makes `backend` available to every element under `name`, for the rest of the process.
+/// Elements only accept names registered before their `code-type` property is set.
+pub fn register(
+    name: &'static str,
+    backend: &'static dyn Backend,
+) -> Result<(), AlreadyRegistered> {
+    let mut registered = REGISTERED.write().unwrap();
+    if name == AUTO
+        || BUILT_IN.iter().any(|(other, _)| *other == name)
+        || registered.iter().any(|(other, _)| *other == name)
+    {
+        return Err(AlreadyRegistered(name));
+    }
+
+    registered.push((name, backend));
+
+    Ok(())
+}
+
+/// Backend registered under `name`, with the name as registered
+pub fn backend(name: &str) -> Option<(&'static str, &'static dyn Backend)> {
+    find_map(|other, backend| (other == name).then_some((other, backend)))
+}
+
+/// Names of every backend, in detection order
+pub fn names() -> Vec<&'static str> {
+    let mut names = Vec::new();
+    find_map(|name, _| {
+        names.push(name);
+        None::<()>
+    });
+
+    names
+}
+
+/// Calls `f` on every backend in detection order, the built-in ones first,
+/// until it returns a value. Nothing is allocated, so it can be used for every frame.
+pub(super) fn find_map<T>(
+    mut f: impl FnMut(&'static str, &'static dyn Backend) -> Option<T>,
+) -> Option<T> {
+    BUILT_IN
+        .iter()
+        .find_map(|(name, backend)| f(name, *backend))
+        .or_else(|| {
+            REGISTERED
+                .read()
+                .unwrap()
+                .iter()
+                .find_map(|(name, backend)| f(name, *backend))
+        })
+}
@@ -0,0 +1,255 @@
+/// Area of an image, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Where the 8 bits components of a pixel are, relative to its first byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// A single luma or gray component, like the Y plane of planar YUV or the Y samples of YUY2
+    Luma { pixel_stride: usize, offset: usize },
+    /// Packed R, G and B components, luma is computed from them
+    Rgb {
+        pixel_stride: usize,
+        offsets: [usize; 3],
+    },
+}
+
+impl Layout {
+    pub const GRAY8: Layout = Layout::Luma {
+        pixel_stride: 1,
+        offset: 0,
+    };
+    pub const RGB: Layout = Layout::Rgb {
+        pixel_stride: 3,
+        offsets: [0, 1, 2],
+    };
+
+    fn pixel_stride(&self) -> usize {
+        match self {
+            Layout::Luma { pixel_stride, .. } | Layout::Rgb { pixel_stride, .. } => *pixel_stride,
+        }
+    }
+
+    /// Offsets of the components that carry the luma
+    fn offsets(&self) -> &[usize] {
+        match self {
+            Layout::Luma { offset, .. } => std::slice::from_ref(offset),
+            Layout::Rgb { offsets, .. } => offsets,
+        }
+    }
+
+    /// Whether `len` bytes hold `height` rows of `width` pixels, `stride` bytes apart,
+    /// without rows or pixels overlapping
+    fn fits(&self, len: usize, width: u32, height: u32, stride: usize) -> bool {
+        let pixel_stride = self.pixel_stride();
+        let last_component = self.offsets().iter().max().copied().unwrap_or_default();
+        if last_component >= pixel_stride || stride < width as usize * pixel_stride {
+            return false;
+        }
+
+        if width == 0 || height == 0 {
+            return true;
+        }
+
+        len > (height as usize - 1) * stride + (width as usize - 1) * pixel_stride + last_component
+    }
+}
+
+/// Read-only image of `width`x`height` pixels in `data`, restricted to an area of it
+#[derive(Debug, Clone, Copy)]
+pub struct Image<'a> {
+    data: &'a [u8],
+    stride: usize,
+    layout: Layout,
+    rect: Rect,
+}
+
+impl<'a> Image<'a> {
+    /// Creates an image whose rows are `stride` bytes apart in `data`.
+    /// Returns `None` if `data` is too short, or if `stride` or the layout make pixels overlap.
+    pub fn new(
+        data: &'a [u8],
+        width: u32,
+        height: u32,
+        stride: usize,
+        layout: Layout,
+    ) -> Option<Self> {
+        if !layout.fits(data.len(), width, height, stride) {
+            return None;
+        }
+
+        Some(Image {
+            data,
+            stride,
+            layout,
+            rect: Rect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+        })
+    }
+
+    /// Restricts the image to `rect`, relative to the current area and clipped to it
+    pub fn crop(self, rect: Rect) -> Self {
+        let x = rect.x.min(self.rect.width);
+        let y = rect.y.min(self.rect.height);
+
+        Image {
+            rect: Rect {
+                x: self.rect.x + x,
+                y: self.rect.y + y,
+                width: rect.width.min(self.rect.width - x),
+                height: rect.height.min(self.rect.height - y),
+            },
+            ..self
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.rect.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.rect.height
+    }
+
+    /// Luma of the pixel at (`x`, `y`), relative to the image area
+    pub fn luma(&self, x: u32, y: u32) -> u8 {
+        let offset = (self.rect.y + y) as usize * self.stride
+            + (self.rect.x + x) as usize * self.layout.pixel_stride();
+
+        match self.layout {
+            Layout::Luma { offset: luma, .. } => self.data[offset + luma],
+            Layout::Rgb {
+                offsets: [r, g, b], ..
+            } => {
+                // BT.601 luma with 8 bits fixed point coefficients
+                let r = self.data[offset + r] as u32;
+                let g = self.data[offset + g] as u32;
+                let b = self.data[offset + b] as u32;
+                ((77 * r + 150 * g + 29 * b) >> 8) as u8
+            }
+        }
+    }
+}
+
+/// Image of `width`x`height` pixels in `data` that codes are drawn into
+#[derive(Debug)]
+pub struct ImageMut<'a> {
+    data: &'a mut [u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    layout: Layout,
+    black: u8,
+    white: u8,
+}
+
+impl<'a> ImageMut<'a> {
+    /// Creates an image whose rows are `stride` bytes apart in `data`, with full range levels.
+    /// Returns `None` if `data` is too short, or if `stride` or the layout make pixels overlap.
+    pub fn new(
+        data: &'a mut [u8],
+        width: u32,
+        height: u32,
+        stride: usize,
+        layout: Layout,
+    ) -> Option<Self> {
+        if !layout.fits(data.len(), width, height, stride) {
+            return None;
+        }
+
+        Some(ImageMut {
+            data,
+            width,
+            height,
+            stride,
+            layout,
+            black: u8::MIN,
+            white: u8::MAX,
+        })
+    }
+
+    /// Sets the component values of black and white, like 16 and 235 for limited range luma
+    pub fn with_levels(self, black: u8, white: u8) -> Self {
+        ImageMut {
+            black,
+            white,
+            ..self
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn black(&self) -> u8 {
+        self.black
+    }
+
+    pub fn white(&self) -> u8 {
+        self.white
+    }
+
+    /// Read-only view of the whole image
+    pub fn as_image(&self) -> Image<'_> {
+        Image {
+            data: self.data,
+            stride: self.stride,
+            layout: self.layout,
+            rect: Rect {
+                x: 0,
+                y: 0,
+                width: self.width,
+                height: self.height,
+            },
+        }
+    }
+
+    /// Applies `f` to the luma components of every pixel of `rect`, clipped to the image
+    fn for_each_component(&mut self, rect: Rect, mut f: impl FnMut(&mut u8)) {
+        let x_end = rect.x.saturating_add(rect.width).min(self.width) as usize;
+        let y_end = rect.y.saturating_add(rect.height).min(self.height) as usize;
+        let (x0, y0) = (rect.x as usize, rect.y as usize);
+        if x0 >= x_end || y0 >= y_end {
+            return;
+        }
+
+        let layout = self.layout;
+        let pixel_stride = layout.pixel_stride();
+        for row in self.data.chunks_mut(self.stride).take(y_end).skip(y0) {
+            for pixel in row[x0 * pixel_stride..]
+                .chunks_mut(pixel_stride)
+                .take(x_end - x0)
+            {
+                layout
+                    .offsets()
+                    .iter()
+                    .for_each(|offset| f(&mut pixel[*offset]));
+            }
+        }
+    }
+
+    /// Sets the luma components of every pixel of `rect` to `value`
+    pub fn fill_rect(&mut self, rect: Rect, value: u8) {
+        self.for_each_component(rect, |component| *component = value);
+    }
+
+    /// Adds `delta` to the luma components of every pixel of `rect`
+    pub fn add_luma(&mut self, rect: Rect, delta: i32) {
+        self.for_each_component(rect, |component| {
+            *component = (*component as i32 + delta).clamp(0, 255) as u8
+        });
+    }
+}
@@ -0,0 +1,146 @@
+//! Timestamp payloads and the codes carrying them, drawn into and read from plain images.
+//!
+//! This module doesn't depend on GStreamer, it is the only one built without the `gst` feature.
+
+#[cfg(feature = "gst")]
+use gst::glib;
+
+mod backend;
+mod image;
+mod payload;
+mod placement;
+mod qr;
+mod strip;
+mod watermark;
+
+pub use backend::{backend, names, register, AlreadyRegistered, Backend, EncoderSettings, AUTO};
+pub use image::{Image, ImageMut, Layout, Rect};
+pub use payload::{Payload, PayloadError, Precision, MAGIC, VERSION};
+pub use placement::{Alignment, Placement};
+pub use qr::{EccLevel, QrDecoder, QrEncoder};
+pub use strip::{StripDecoder, StripEncoder};
+pub use watermark::{WatermarkDecoder, WatermarkEncoder};
+
+/// Reason why a frame could not be measured
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(
+    feature = "gst",
+    derive(glib::Enum),
+    enum_type(name = "GstQRTimeStampSinkDecodeFailure")
+)]
+#[repr(u32)]
+pub enum DecodeFailure {
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(name = "No grid: No qrcode was found in the frame", nick = "no-grid")
+    )]
+    NoGrid = 0,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(
+            name = "Grid decode: A qrcode was found but could not be decoded or failed ECC",
+            nick = "grid-decode"
+        )
+    )]
+    GridDecode = 1,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(
+            name = "Unparseable: The qrcode has our magic prefix but its content can't be parsed",
+            nick = "unparseable"
+        )
+    )]
+    Unparseable = 2,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(
+            name = "Foreign magic: The qrcode was not generated by qrtimestampsrc",
+            nick = "foreign-magic"
+        )
+    )]
+    ForeignMagic = 3,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(
+            name = "Future timestamp: The decoded timestamp is ahead of the current time by more than the tolerance",
+            nick = "future-timestamp"
+        )
+    )]
+    FutureTimestamp = 4,
+}
+
+/// Reason why a frame could not be decoded, with the content of its code if any
+pub type Failure = (DecodeFailure, Option<String>);
+
+/// Payload read from a frame, with the properties of the code that carried it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    pub payload: Payload,
+    /// Error correction level, for codes that have one
+    pub ecc_level: Option<EccLevel>,
+    /// Version of the symbology, for codes that have one
+    pub version: Option<u32>,
+}
+
+/// Code drawn into an image by a [`TimestampEncoder`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drawn {
+    /// Area of the code
+    pub rect: Rect,
+    /// Why the code doesn't follow the drawing settings, e.g. when the image is too small for them
+    pub warning: Option<String>,
+}
+
+impl From<Rect> for Drawn {
+    fn from(rect: Rect) -> Self {
+        Drawn {
+            rect,
+            warning: None,
+        }
+    }
+}
+
+/// Draws timestamp payloads into images, implementations hold their own drawing settings
+pub trait TimestampEncoder: Send + Sync {
+    /// Draws `payload` into `image`, returning the area of the code
+    fn encode(
+        &self,
+        image: &mut ImageMut,
+        payload: &Payload,
+    ) -> Result<Drawn, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Whether the code only changes the luma of the image, keeping the colors of its content.
+    /// Other codes are drawn in black and white, with neutral chroma.
+    fn keeps_chroma(&self) -> bool {
+        false
+    }
+}
+
+/// Reads timestamp payloads from the luma of images
+pub trait TimestampDecoder: Sync {
+    /// Looks for a code in `image` and decodes its payload.
+    /// On failure, returns the most specific reason and the content of the code if any.
+    fn decode(&self, image: &Image) -> Result<Decoded, Failure>;
+}
+
+/// Decodes the code of the backend named `code_type` in `image`, returning the name of the
+/// backend. With [`AUTO`], every backend is tried in turn until one finds its code.
+/// On failure, returns the first reason more specific than no code being found.
+pub fn detect(image: &Image, code_type: &str) -> Result<(Decoded, &'static str), Failure> {
+    let mut failure = (DecodeFailure::NoGrid, None);
+    let decoded = backend::find_map(|name, backend| {
+        if code_type != AUTO && code_type != name {
+            return None;
+        }
+
+        match backend.decoder().decode(image) {
+            Ok(decoded) => return Some((decoded, name)),
+            Err(error) if failure.0 == DecodeFailure::NoGrid => failure = error,
+            Err(_) => (),
+        }
+
+        None
+    });
+
+    decoded.ok_or(failure)
+}
@@ -0,0 +1,274 @@
+use std::fmt;
+
+#[cfg(feature = "gst")]
+use gst::glib;
+
+/// Prefix identifying the qrcodes generated by qrtimestampsrc
+pub const MAGIC: &str = "QRTS";
+/// Latest version of the payload format
+pub const VERSION: u32 = 2;
+/// Separator between the payload fields
+const SEPARATOR: char = ':';
+/// Placeholder for optional fields that are not set
+const NONE: &str = "-";
+/// First byte of the binary payload
+const BINARY_MAGIC: u8 = b'Q';
+/// Size of the binary payload, in bytes
+pub const BINARY_LEN: usize = 18;
+/// Number of digits of the legacy payloads, unix timestamps in milliseconds from 1973 to 2554.
+/// Other numbers are most likely codes of something else in the scene, like product numbers.
+const LEGACY_DIGITS: std::ops::RangeInclusive<usize> = 12..=14;
+
+/// Unit of the timestamp encoded in the payload
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(
+    feature = "gst",
+    derive(glib::Enum),
+    enum_type(name = "GstQRTimeStampPrecision")
+)]
+#[repr(u32)]
+pub enum Precision {
+    #[cfg_attr(feature = "gst", enum_value(name = "Milliseconds", nick = "ms"))]
+    Milliseconds = 3,
+    #[default]
+    #[cfg_attr(feature = "gst", enum_value(name = "Microseconds", nick = "us"))]
+    Microseconds = 6,
+    #[cfg_attr(feature = "gst", enum_value(name = "Nanoseconds", nick = "ns"))]
+    Nanoseconds = 9,
+}
+
+impl Precision {
+    /// Number of decimal digits of a second, which is how the precision is encoded
+    fn digits(self) -> u32 {
+        self as u32
+    }
+
+    fn from_digits(digits: u32) -> Option<Self> {
+        match digits {
+            3 => Some(Precision::Milliseconds),
+            6 => Some(Precision::Microseconds),
+            9 => Some(Precision::Nanoseconds),
+            _ => None,
+        }
+    }
+
+    /// Nanoseconds in one unit of this precision
+    fn unit(self) -> u64 {
+        10u64.pow(9 - self.digits())
+    }
+
+    /// Truncates `time`, in nanoseconds, to this precision
+    pub fn truncate(self, time: u64) -> u64 {
+        time / self.unit() * self.unit()
+    }
+}
+
+/// Content of the qrcodes exchanged between qrtimestampsrc and qrtimestampsink
+///
+/// Version 2 is encoded as `QRTS:2:<timestamp>:<sequence>:<pts>:<stream id>:<precision>`, with
+/// `-` as pts when the buffer has none and the precision as the number of decimal digits of a
+/// second in the timestamp (3, 6 or 9). Version 1 has no precision field and is always in
+/// milliseconds, as are legacy payloads made only of the timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Payload {
+    /// Version of the format, `0` for legacy payloads
+    pub version: u32,
+    /// Time at which the frame was created in nanoseconds, truncated to `precision`
+    pub timestamp: u64,
+    pub precision: Precision,
+    /// Frame number since the source started
+    pub sequence: Option<u64>,
+    /// Presentation timestamp of the buffer carrying the frame, in nanoseconds
+    pub pts: Option<u64>,
+    /// Identifier of the source that generated the frame
+    pub stream_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayloadError {
+    /// The content does not start with our magic prefix
+    ForeignMagic,
+    /// The content was generated by a newer version of the format
+    UnsupportedVersion(u32),
+    /// The content has our magic prefix but its fields can't be parsed
+    Malformed,
+    /// The checksum of a binary payload doesn't match its content
+    Checksum,
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadError::ForeignMagic => write!(f, "Content is not prefixed by {MAGIC}"),
+            PayloadError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported payload version {version}")
+            }
+            PayloadError::Malformed => write!(f, "Malformed payload"),
+            PayloadError::Checksum => write!(f, "Payload checksum mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for PayloadError {}
+
+impl Payload {
+    pub fn new(
+        timestamp: u64,
+        precision: Precision,
+        sequence: u64,
+        pts: Option<u64>,
+        stream_id: u32,
+    ) -> Self {
+        Payload {
+            version: VERSION,
+            timestamp: precision.truncate(timestamp),
+            precision,
+            sequence: Some(sequence),
+            pts,
+            stream_id: Some(stream_id),
+        }
+    }
+
+    /// Serializes the payload in the latest format version
+    pub fn encode(&self) -> String {
+        let pts = self
+            .pts
+            .map(|pts| pts.to_string())
+            .unwrap_or_else(|| NONE.to_string());
+
+        format!(
+            "{MAGIC}{SEPARATOR}{VERSION}{SEPARATOR}{timestamp}{SEPARATOR}{sequence}{SEPARATOR}{pts}{SEPARATOR}{stream_id}{SEPARATOR}{precision}",
+            timestamp = self.timestamp / self.precision.unit(),
+            sequence = self.sequence.unwrap_or_default(),
+            stream_id = self.stream_id.unwrap_or_default(),
+            precision = self.precision.digits(),
+        )
+    }
+
+    /// Parses the content of a qrcode, accepting the legacy format
+    pub fn decode(content: &str) -> Result<Self, PayloadError> {
+        if LEGACY_DIGITS.contains(&content.len())
+            && content.bytes().all(|byte| byte.is_ascii_digit())
+        {
+            let timestamp = content.parse().map_err(|_| PayloadError::Malformed)?;
+
+            return Ok(Payload {
+                version: 0,
+                timestamp: nanoseconds(timestamp, Precision::Milliseconds.unit())?,
+                precision: Precision::Milliseconds,
+                sequence: None,
+                pts: None,
+                stream_id: None,
+            });
+        }
+
+        let mut fields = content.split(SEPARATOR);
+
+        if fields.next() != Some(MAGIC) {
+            return Err(PayloadError::ForeignMagic);
+        }
+
+        let version = parse_field::<u32>(fields.next())?;
+        if version == 0 || version > VERSION {
+            return Err(PayloadError::UnsupportedVersion(version));
+        }
+
+        let timestamp = parse_field::<u64>(fields.next())?;
+        let sequence = parse_field(fields.next())?;
+        let pts = match fields.next() {
+            Some(NONE) => None,
+            field => Some(nanoseconds(parse_field(field)?, 1)?),
+        };
+        let stream_id = parse_field(fields.next())?;
+        let precision = match version {
+            1 => Precision::Milliseconds,
+            _ => Precision::from_digits(parse_field(fields.next())?)
+                .ok_or(PayloadError::Malformed)?,
+        };
+        if fields.next().is_some() {
+            return Err(PayloadError::Malformed);
+        }
+
+        Ok(Payload {
+            version,
+            timestamp: nanoseconds(timestamp, precision.unit())?,
+            precision,
+            sequence: Some(sequence),
+            pts,
+            stream_id: Some(stream_id),
+        })
+    }
+
+    /// Serializes the payload in the compact binary format used by codes that carry few bits.
+    ///
+    /// The format is the `Q` magic byte, the precision digits, the timestamp as a big-endian
+    /// `u64`, the sequence as a `u32`, the stream id as a `u16` and a CRC-16/CCITT-FALSE of the
+    /// previous bytes. The pts is not included, and the sequence and stream id are truncated.
+    pub fn encode_binary(&self) -> [u8; BINARY_LEN] {
+        let mut bytes = [0; BINARY_LEN];
+        bytes[0] = BINARY_MAGIC;
+        bytes[1] = self.precision.digits() as u8;
+        bytes[2..10].copy_from_slice(&(self.timestamp / self.precision.unit()).to_be_bytes());
+        bytes[10..14].copy_from_slice(&(self.sequence.unwrap_or_default() as u32).to_be_bytes());
+        bytes[14..16].copy_from_slice(&(self.stream_id.unwrap_or_default() as u16).to_be_bytes());
+        let crc = crc16(&bytes[..BINARY_LEN - 2]);
+        bytes[BINARY_LEN - 2..].copy_from_slice(&crc.to_be_bytes());
+
+        bytes
+    }
+
+    /// Parses a payload serialized by [`Payload::encode_binary`]
+    pub fn decode_binary(bytes: &[u8; BINARY_LEN]) -> Result<Self, PayloadError> {
+        if bytes[0] != BINARY_MAGIC {
+            return Err(PayloadError::ForeignMagic);
+        }
+
+        let (content, crc) = bytes.split_at(BINARY_LEN - 2);
+        if crc16(content).to_be_bytes() != crc {
+            return Err(PayloadError::Checksum);
+        }
+
+        let precision = Precision::from_digits(bytes[1] as u32).ok_or(PayloadError::Malformed)?;
+        let timestamp = u64::from_be_bytes(bytes[2..10].try_into().unwrap());
+        let sequence = u32::from_be_bytes(bytes[10..14].try_into().unwrap());
+        let stream_id = u16::from_be_bytes(bytes[14..16].try_into().unwrap());
+
+        Ok(Payload {
+            version: VERSION,
+            timestamp: nanoseconds(timestamp, precision.unit())?,
+            precision,
+            sequence: Some(sequence as u64),
+            pts: None,
+            stream_id: Some(stream_id as u32),
+        })
+    }
+}
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF, no reflection
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+fn parse_field<T: std::str::FromStr>(field: Option<&str>) -> Result<T, PayloadError> {
+    field
+        .ok_or(PayloadError::Malformed)?
+        .parse()
+        .map_err(|_| PayloadError::Malformed)
+}
+
+/// Converts `value` units of `unit` nanoseconds to nanoseconds.
+/// `u64::MAX` is rejected too, it is not a valid `GstClockTime`.
+fn nanoseconds(value: u64, unit: u64) -> Result<u64, PayloadError> {
+    match value.checked_mul(unit) {
+        Some(nseconds) if nseconds != u64::MAX => Ok(nseconds),
+        _ => Err(PayloadError::Malformed),
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(feature = "gst")]
+use gst::glib;
+
+use super::Rect;
+
+/// Where the code is placed in the frame when no explicit position is set
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(
+    feature = "gst",
+    derive(glib::Enum),
+    enum_type(name = "GstQRTimeStampAlignment")
+)]
+#[repr(u32)]
+pub enum Alignment {
+    #[default]
+    #[cfg_attr(feature = "gst", enum_value(name = "Center", nick = "center"))]
+    Center = 0,
+    #[cfg_attr(feature = "gst", enum_value(name = "Top left", nick = "top-left"))]
+    TopLeft = 1,
+    #[cfg_attr(feature = "gst", enum_value(name = "Top", nick = "top"))]
+    Top = 2,
+    #[cfg_attr(feature = "gst", enum_value(name = "Top right", nick = "top-right"))]
+    TopRight = 3,
+    #[cfg_attr(feature = "gst", enum_value(name = "Left", nick = "left"))]
+    Left = 4,
+    #[cfg_attr(feature = "gst", enum_value(name = "Right", nick = "right"))]
+    Right = 5,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(name = "Bottom left", nick = "bottom-left")
+    )]
+    BottomLeft = 6,
+    #[cfg_attr(feature = "gst", enum_value(name = "Bottom", nick = "bottom"))]
+    Bottom = 7,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(name = "Bottom right", nick = "bottom-right")
+    )]
+    BottomRight = 8,
+}
+
+/// Position and size of the code in the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Horizontal position of the left edge, negative to follow `alignment`
+    pub x: i32,
+    /// Vertical position of the top edge, negative to follow `alignment`
+    pub y: i32,
+    pub alignment: Alignment,
+    /// Side of the code in pixels, `0` to follow `scale`
+    pub size: u32,
+    /// Side of the code as a fraction of the smallest frame dimension
+    pub scale: f64,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            x: -1,
+            y: -1,
+            alignment: Alignment::Center,
+            size: 0,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Placement {
+    /// Square area that the code occupies in a `width`x`height` image, always inside of it
+    pub fn rect(&self, width: u32, height: u32) -> Rect {
+        let max_size = width.min(height);
+        let size = if self.size > 0 {
+            self.size
+        } else {
+            (max_size as f64 * self.scale).round() as u32
+        }
+        .clamp(1, max_size.max(1));
+
+        let free_width = width.saturating_sub(size);
+        let free_height = height.saturating_sub(size);
+
+        use Alignment::*;
+        let (x, y) = match self.alignment {
+            TopLeft => (0, 0),
+            Top => (free_width / 2, 0),
+            TopRight => (free_width, 0),
+            Left => (0, free_height / 2),
+            Center => (free_width / 2, free_height / 2),
+            Right => (free_width, free_height / 2),
+            BottomLeft => (0, free_height),
+            Bottom => (free_width / 2, free_height),
+            BottomRight => (free_width, free_height),
+        };
+
+        let x = u32::try_from(self.x).map_or(x, |x| x.min(free_width));
+        let y = u32::try_from(self.y).map_or(y, |y| y.min(free_height));
+
+        Rect {
+            x,
+            y,
+            width: size,
+            height: size,
+        }
+    }
+}
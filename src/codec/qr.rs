@@ -0,0 +1,212 @@
+#[cfg(feature = "gst")]
+use gst::glib;
+
+use super::{
+    DecodeFailure, Decoded, Drawn, Failure, Image, ImageMut, Payload, PayloadError, Placement,
+    Rect, TimestampDecoder, TimestampEncoder,
+};
+
+/// Draws the text form of the payload as a qrcode
+#[derive(Debug, Clone, Copy)]
+pub struct QrEncoder {
+    pub placement: Placement,
+    pub ecc_level: EccLevel,
+    /// Minimum size of each module in pixels
+    pub module_size: u32,
+    /// Width of the light border around the qrcode, in modules
+    pub quiet_zone: u32,
+    /// Fixed qrcode version, `0` for the smallest one that fits the payload
+    pub version: u32,
+}
+
+impl TimestampEncoder for QrEncoder {
+    fn encode(
+        &self,
+        image: &mut ImageMut,
+        payload: &Payload,
+    ) -> Result<Drawn, Box<dyn std::error::Error + Send + Sync>> {
+        let data = payload.encode();
+        let ecc_level = qrcode::EcLevel::from(self.ecc_level);
+        let code = match self.version {
+            0 => qrcode::QrCode::with_error_correction_level(data, ecc_level),
+            version => qrcode::QrCode::with_version(
+                data,
+                qrcode::Version::Normal(version as i16),
+                ecc_level,
+            ),
+        }?;
+
+        // Grow the qrcode if its modules would be smaller than the minimum module size
+        let (width, height) = (image.width(), image.height());
+        let mut placement = self.placement;
+        let total_modules = (code.width() as u32).saturating_add(self.quiet_zone.saturating_mul(2));
+        let minimum_size = total_modules.saturating_mul(self.module_size);
+        if placement.rect(width, height).width < minimum_size {
+            placement.size = minimum_size;
+        }
+        let qr_rect = placement.rect(width, height);
+
+        draw_qrcode(image, qr_rect, &code, self.quiet_zone);
+
+        // The qrcode is kept inside of the image, with smaller modules if needed
+        let warning = (qr_rect.width < minimum_size).then(|| {
+            format!(
+                "{width}x{height} image is too small for a qrcode of {total_modules} modules of {} pixels",
+                self.module_size
+            )
+        });
+
+        Ok(Drawn {
+            rect: qr_rect,
+            warning,
+        })
+    }
+}
+
+/// Looks for our qrcode in the frame, other qrcodes in the scene are skipped
+pub struct QrDecoder;
+
+impl TimestampDecoder for QrDecoder {
+    fn decode(&self, image: &Image) -> Result<Decoded, Failure> {
+        let mut qrcode_image = rqrr::PreparedImage::prepare_from_greyscale(
+            image.width() as usize,
+            image.height() as usize,
+            |x, y| image.luma(x as u32, y as u32),
+        );
+
+        let grids = qrcode_image.detect_grids();
+        if grids.is_empty() {
+            return Err((DecodeFailure::NoGrid, None));
+        }
+
+        let mut failure = (DecodeFailure::GridDecode, None);
+        for grid in grids {
+            // Grids that fail ECC are reported as grid-decode unless another one is ours
+            let Ok((meta, content)) = grid.decode() else {
+                continue;
+            };
+
+            match Payload::decode(&content) {
+                Ok(payload) => {
+                    return Ok(Decoded {
+                        payload,
+                        ecc_level: EccLevel::from_format_bits(meta.ecc_level),
+                        version: Some(meta.version.0 as u32),
+                    })
+                }
+                Err(PayloadError::ForeignMagic) => {
+                    // Only report a foreign qrcode if none of ours was found
+                    if failure.0 == DecodeFailure::GridDecode {
+                        failure = (DecodeFailure::ForeignMagic, Some(content));
+                    }
+                }
+                Err(_) => failure = (DecodeFailure::Unparseable, Some(content)),
+            }
+        }
+
+        Err(failure)
+    }
+}
+
+/// Error correction level of the qrcode, higher levels survive more damage but need more modules
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(
+    feature = "gst",
+    derive(glib::Enum),
+    enum_type(name = "GstQRTimeStampEccLevel")
+)]
+#[repr(u32)]
+pub enum EccLevel {
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(name = "L: Recovers 7% of the data", nick = "l")
+    )]
+    L = 0,
+    #[default]
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(name = "M: Recovers 15% of the data", nick = "m")
+    )]
+    M = 1,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(name = "Q: Recovers 25% of the data", nick = "q")
+    )]
+    Q = 2,
+    #[cfg_attr(
+        feature = "gst",
+        enum_value(name = "H: Recovers 30% of the data", nick = "h")
+    )]
+    H = 3,
+}
+
+impl EccLevel {
+    /// Converts the two error correction bits of the qrcode format information
+    pub fn from_format_bits(bits: u16) -> Option<Self> {
+        match bits {
+            0b01 => Some(EccLevel::L),
+            0b00 => Some(EccLevel::M),
+            0b11 => Some(EccLevel::Q),
+            0b10 => Some(EccLevel::H),
+            _ => None,
+        }
+    }
+}
+
+impl From<EccLevel> for qrcode::EcLevel {
+    fn from(level: EccLevel) -> Self {
+        match level {
+            EccLevel::L => qrcode::EcLevel::L,
+            EccLevel::M => qrcode::EcLevel::M,
+            EccLevel::Q => qrcode::EcLevel::Q,
+            EccLevel::H => qrcode::EcLevel::H,
+        }
+    }
+}
+
+/// Draws `code` with a quiet zone of `quiet_zone` modules around it, scaled to fill `rect`.
+/// Each module is scaled with nearest-neighbour, so modules may differ by one pixel in size.
+fn draw_qrcode(image: &mut ImageMut, rect: Rect, code: &qrcode::QrCode, quiet_zone: u32) {
+    // Everything that is not a dark module is light, the quiet zone included
+    let (dark, light) = (image.black(), image.white());
+    image.fill_rect(rect, light);
+
+    let modules = code.width() as u32;
+    let total_modules = modules as u64 + 2 * quiet_zone as u64;
+    let edge = |module: u32, length: u32| {
+        ((module as u64 + quiet_zone as u64) * length as u64 / total_modules) as u32
+    };
+
+    for module_y in 0..modules {
+        let y = edge(module_y, rect.height);
+        let height = edge(module_y + 1, rect.height) - y;
+        if height == 0 {
+            continue;
+        }
+
+        // Draw each horizontal run of dark modules at once
+        let mut module_x = 0;
+        while module_x < modules {
+            if code[(module_x as usize, module_y as usize)] != qrcode::Color::Dark {
+                module_x += 1;
+                continue;
+            }
+
+            let run_start = module_x;
+            while module_x < modules
+                && code[(module_x as usize, module_y as usize)] == qrcode::Color::Dark
+            {
+                module_x += 1;
+            }
+
+            let x = edge(run_start, rect.width);
+            let run = Rect {
+                x: rect.x + x,
+                y: rect.y + y,
+                width: edge(module_x, rect.width) - x,
+                height,
+            };
+            image.fill_rect(run, dark);
+        }
+    }
+}
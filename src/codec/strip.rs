@@ -0,0 +1,165 @@
+use super::payload::BINARY_LEN;
+use super::{
+    DecodeFailure, Decoded, Drawn, Failure, Image, ImageMut, Payload, PayloadError, Placement,
+    Rect, TimestampDecoder, TimestampEncoder,
+};
+
+/// Blocks before the payload, `true` for dark ones
+const START: [bool; 3] = [true, false, true];
+/// Blocks after the payload, different from the start so mirrored strips are rejected
+const STOP: [bool; 4] = [true, true, false, true];
+const BITS: usize = BINARY_LEN * 8;
+/// Blocks between the first and the last dark ones
+const CODE_BLOCKS: usize = START.len() + BITS + STOP.len();
+/// Blocks of the strip, with a light quiet block at each end
+const BLOCKS: usize = CODE_BLOCKS + 2;
+/// Scanlines sampled across the frame height when looking for the strip
+const SCANLINES: u32 = 64;
+/// Smallest luma difference between the dark and light blocks of a strip
+const MIN_CONTRAST: u8 = 64;
+
+/// Color of every block of the code part of the strip, `true` for dark
+fn code_blocks(payload: &Payload) -> impl Iterator<Item = bool> {
+    let bytes = payload.encode_binary();
+    let bits = (0..BITS).map(move |bit| (bytes[bit / 8] >> (7 - bit % 8)) & 1 == 1);
+
+    START.into_iter().chain(bits).chain(STOP)
+}
+
+/// Draws the binary form of the payload as a row of blocks across the frame width
+#[derive(Debug, Clone, Copy)]
+pub struct StripEncoder {
+    /// The strip occupies the rows of the square placed in the image
+    pub placement: Placement,
+}
+
+impl TimestampEncoder for StripEncoder {
+    fn encode(
+        &self,
+        image: &mut ImageMut,
+        payload: &Payload,
+    ) -> Result<Drawn, Box<dyn std::error::Error + Send + Sync>> {
+        let (width, height) = (image.width(), image.height());
+        let rows = self.placement.rect(width, height);
+        let rect = Rect {
+            x: 0,
+            y: rows.y,
+            width,
+            height: rows.height,
+        };
+
+        draw(image, rect, payload);
+
+        Ok(rect.into())
+    }
+}
+
+/// Draws the binary form of `payload` as a row of blocks filling `rect`
+fn draw(image: &mut ImageMut, rect: Rect, payload: &Payload) {
+    let (dark, light) = (image.black(), image.white());
+    image.fill_rect(rect, light);
+
+    let edge = |block: usize| rect.x + (block as u64 * rect.width as u64 / BLOCKS as u64) as u32;
+
+    // The first block is the light quiet one
+    for (block, is_dark) in code_blocks(payload).enumerate() {
+        if !is_dark {
+            continue;
+        }
+
+        let x = edge(block + 1);
+        let block = Rect {
+            x,
+            y: rect.y,
+            width: edge(block + 2) - x,
+            height: rect.height,
+        };
+        image.fill_rect(block, dark);
+    }
+}
+
+/// Reads the strip crossed by the row `y`, returning `None` if there is none.
+/// The ends of the strip are its first and last dark blocks, so it can be cropped or scaled.
+fn read_line(image: &Image, y: u32) -> Option<Result<Payload, PayloadError>> {
+    let width = image.width();
+    let (min, max) = (0..width)
+        .map(|x| image.luma(x, y))
+        .fold((u8::MAX, u8::MIN), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+    if max.saturating_sub(min) < MIN_CONTRAST {
+        return None;
+    }
+
+    let threshold = ((min as u32 + max as u32) / 2) as u8;
+    let is_dark = |x: u32| image.luma(x, y) < threshold;
+
+    let first = (0..width).find(|x| is_dark(*x))?;
+    let last = (0..width).rev().find(|x| is_dark(*x))?;
+    let block_width = (last + 1 - first) as f64 / CODE_BLOCKS as f64;
+    if block_width < 1.0 {
+        return None;
+    }
+
+    // Sample the center of each block, its edges are blurred by scaling and compression
+    let mut blocks =
+        (0..CODE_BLOCKS).map(|block| is_dark(first + ((block as f64 + 0.5) * block_width) as u32));
+
+    if !START.iter().all(|start| blocks.next() == Some(*start)) {
+        return None;
+    }
+
+    let mut bytes = [0; BINARY_LEN];
+    for bit in 0..BITS {
+        if blocks.next() == Some(true) {
+            bytes[bit / 8] |= 1 << (7 - bit % 8);
+        }
+    }
+
+    if !STOP.iter().all(|stop| blocks.next() == Some(*stop)) {
+        return None;
+    }
+
+    Some(Payload::decode_binary(&bytes))
+}
+
+/// Reads the strips drawn by [`StripEncoder`]
+pub struct StripDecoder;
+
+impl TimestampDecoder for StripDecoder {
+    fn decode(&self, image: &Image) -> Result<Decoded, Failure> {
+        match extract(image) {
+            Some(Ok(payload)) => Ok(Decoded {
+                payload,
+                ecc_level: None,
+                version: None,
+            }),
+            // The markers were found, so the strip is ours but its bits are damaged
+            Some(Err(PayloadError::Checksum | PayloadError::ForeignMagic)) => {
+                Err((DecodeFailure::GridDecode, None))
+            }
+            Some(Err(_)) => Err((DecodeFailure::Unparseable, None)),
+            None => Err((DecodeFailure::NoGrid, None)),
+        }
+    }
+}
+
+/// Looks for a strip on a few scanlines spread over the frame height.
+/// Returns `None` if no strip was found, or the payload of the first valid one.
+fn extract(image: &Image) -> Option<Result<Payload, PayloadError>> {
+    let height = image.height();
+    let scanlines = SCANLINES.min(height);
+
+    let mut result = None;
+    for scanline in 0..scanlines {
+        let y = ((2 * scanline + 1) as u64 * height as u64 / (2 * scanlines) as u64) as u32;
+
+        match read_line(image, y) {
+            Some(Ok(payload)) => return Some(Ok(payload)),
+            Some(Err(error)) => result = Some(Err(error)),
+            None => (),
+        }
+    }
+
+    result
+}
@@ -0,0 +1,150 @@
+use super::payload::BINARY_LEN;
+use super::{
+    DecodeFailure, Decoded, Drawn, Failure, Image, ImageMut, Payload, PayloadError, Rect,
+    TimestampDecoder, TimestampEncoder,
+};
+
+/// The frame is split in a grid of cells, each pair of horizontally adjacent cells carries a bit.
+/// The grid follows the frame size, so the watermark survives scaling.
+const COLUMNS: u32 = 80;
+const ROWS: u32 = 45;
+const BITS: usize = BINARY_LEN * 8;
+/// Largest luma difference a cell pair adds to its bit, so that edges of the picture
+/// falling between the two cells can't outweigh the other pairs carrying the bit
+const MAX_CONTRIBUTION: f64 = 16.0;
+
+/// Area of the cell at (`column`, `row`) in a `width`x`height` frame
+fn cell(column: u32, row: u32, width: u32, height: u32) -> Rect {
+    let edge =
+        |index: u32, cells: u32, length: u32| (index as u64 * length as u64 / cells as u64) as u32;
+
+    let x = edge(column, COLUMNS, width);
+    let y = edge(row, ROWS, height);
+
+    Rect {
+        x,
+        y,
+        width: edge(column + 1, COLUMNS, width) - x,
+        height: edge(row + 1, ROWS, height) - y,
+    }
+}
+
+/// Left column, row, payload bit and sign of every cell pair.
+/// Signs are pseudo-random, so the pattern looks like noise rather than a repeating texture.
+fn pairs() -> impl Iterator<Item = (u32, u32, usize, i32)> {
+    let mut seed: u32 = 0x2545_f491;
+
+    (0..ROWS)
+        .flat_map(|row| (0..COLUMNS / 2).map(move |pair| (pair * 2, row)))
+        .enumerate()
+        .map(move |(index, (column, row))| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let sign = if seed >> 31 == 0 { 1 } else { -1 };
+
+            (column, row, index % BITS, sign)
+        })
+}
+
+/// Hides the binary form of the payload in the whole frame, keeping its content visible
+#[derive(Debug, Clone, Copy)]
+pub struct WatermarkEncoder {
+    /// Largest luma change, in 8 bits levels
+    pub strength: u32,
+}
+
+impl TimestampEncoder for WatermarkEncoder {
+    fn encode(
+        &self,
+        image: &mut ImageMut,
+        payload: &Payload,
+    ) -> Result<Drawn, Box<dyn std::error::Error + Send + Sync>> {
+        let bytes = payload.encode_binary();
+        let (width, height) = (image.width(), image.height());
+
+        for (column, row, bit, sign) in pairs() {
+            let value = if (bytes[bit / 8] >> (7 - bit % 8)) & 1 == 1 {
+                1
+            } else {
+                -1
+            };
+            let delta = sign * value * self.strength as i32;
+
+            image.add_luma(cell(column, row, width, height), delta);
+            image.add_luma(cell(column + 1, row, width, height), -delta);
+        }
+
+        Ok(Rect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+        .into())
+    }
+
+    fn keeps_chroma(&self) -> bool {
+        true
+    }
+}
+
+/// Mean luma of the center of `cell`, leaving out its borders which are blurred by scaling and compression
+fn center_mean(image: &Image, cell: Rect) -> f64 {
+    let (margin_x, margin_y) = (cell.width / 4, cell.height / 4);
+
+    let mut sum = 0u64;
+    let mut count = 0u64;
+    for y in cell.y + margin_y..cell.y + cell.height - margin_y {
+        for x in cell.x + margin_x..cell.x + cell.width - margin_x {
+            sum += image.luma(x, y) as u64;
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return 0.0;
+    }
+
+    sum as f64 / count as f64
+}
+
+/// Recovers the payload hidden by [`WatermarkEncoder`]
+pub struct WatermarkDecoder;
+
+impl TimestampDecoder for WatermarkDecoder {
+    fn decode(&self, image: &Image) -> Result<Decoded, Failure> {
+        let payload = extract(image).map_err(|error| match error {
+            // Without our magic byte, there is most likely no watermark at all
+            PayloadError::ForeignMagic => (DecodeFailure::NoGrid, None),
+            PayloadError::Checksum => (DecodeFailure::GridDecode, None),
+            _ => (DecodeFailure::Unparseable, None),
+        })?;
+
+        Ok(Decoded {
+            payload,
+            ecc_level: None,
+            version: None,
+        })
+    }
+}
+
+/// Frames without a watermark fail with [`PayloadError::ForeignMagic`] or [`PayloadError::Checksum`]
+fn extract(image: &Image) -> Result<Payload, PayloadError> {
+    let (width, height) = (image.width(), image.height());
+
+    let mut sums = [0.0; BITS];
+    for (column, row, bit, sign) in pairs() {
+        let left = center_mean(image, cell(column, row, width, height));
+        let right = center_mean(image, cell(column + 1, row, width, height));
+
+        sums[bit] += (sign as f64 * (left - right)).clamp(-MAX_CONTRIBUTION, MAX_CONTRIBUTION);
+    }
+
+    let mut bytes = [0; BINARY_LEN];
+    for (bit, sum) in sums.iter().enumerate() {
+        if *sum > 0.0 {
+            bytes[bit / 8] |= 1 << (7 - bit % 8);
+        }
+    }
+
+    Payload::decode_binary(&bytes)
+}
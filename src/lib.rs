@@ -1,16 +1,42 @@
+pub mod codec;
+
+#[cfg(feature = "gst")]
+mod analyzer;
+#[cfg(feature = "gst")]
+mod luma;
+#[cfg(feature = "gst")]
+mod qroverlay;
+#[cfg(feature = "gst")]
+mod qrprobe;
+#[cfg(feature = "gst")]
 mod qrsink;
+#[cfg(feature = "gst")]
 mod qrsrc;
+#[cfg(feature = "gst")]
+mod render;
+#[cfg(feature = "gst")]
+mod stamp;
+#[cfg(feature = "gst")]
+mod stats;
+#[cfg(feature = "gst")]
+mod timecode;
+#[cfg(feature = "gst")]
+mod timesource;
 
 pub const MINIMUM_SIZE: u32 = 100;
 pub const MINIMUM_FPS: i32 = 1;
 pub const MAXIMUM_FPS: i32 = 1000;
 
+#[cfg(feature = "gst")]
 fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    qroverlay::register(plugin)?;
+    qrprobe::register(plugin)?;
     qrsink::register(plugin)?;
     qrsrc::register(plugin)?;
     Ok(())
 }
 
+#[cfg(feature = "gst")]
 gst::plugin_define!(
     qrtimestamp, // This name should be the lib name in Cargo.toml without the gst prefix
     env!("CARGO_PKG_DESCRIPTION"),
@@ -0,0 +1,60 @@
+use gst_video::prelude::*;
+
+use crate::codec::{Image, Layout, Rect};
+
+/// Raw video formats whose luma can be read, all of them with 8 bits per component
+pub const FORMATS: [gst_video::VideoFormat; 7] = [
+    gst_video::VideoFormat::Rgb,
+    gst_video::VideoFormat::Gray8,
+    gst_video::VideoFormat::I420,
+    gst_video::VideoFormat::Nv12,
+    gst_video::VideoFormat::Yuy2,
+    gst_video::VideoFormat::Rgbx,
+    gst_video::VideoFormat::Bgrx,
+];
+
+/// Layout of the components carrying the luma in the first plane of frames of `finfo`
+pub fn layout(finfo: &gst_video::VideoFormatInfo) -> Layout {
+    let poffset = finfo.poffset();
+    let pixel_stride = finfo.pixel_stride()[0] as usize;
+
+    if finfo.is_rgb() {
+        Layout::Rgb {
+            pixel_stride,
+            offsets: [
+                poffset[0] as usize,
+                poffset[1] as usize,
+                poffset[2] as usize,
+            ],
+        }
+    } else {
+        Layout::Luma {
+            pixel_stride,
+            offset: poffset[0] as usize,
+        }
+    }
+}
+
+/// Read-only luma access to a mapped frame, restricted to `crop` or to the whole frame if `None`.
+/// Strides and plane offsets come from the frame, so `GstVideoMeta` is honoured.
+/// Returns `None` if the frame format is not supported.
+pub fn image<'a>(
+    frame: &'a gst_video::VideoFrameRef<&'a gst::BufferRef>,
+    crop: Option<Rect>,
+) -> Option<Image<'a>> {
+    if !FORMATS.contains(&frame.format()) {
+        return None;
+    }
+
+    let finfo = frame.format_info();
+    let plane = finfo.plane()[0];
+    let data = frame.plane_data(plane).ok()?;
+    let stride = frame.plane_stride()[plane as usize] as usize;
+    let image = Image::new(data, frame.width(), frame.height(), stride, layout(&finfo))?;
+
+    // Crop metas may come from elements that don't clip them to the frame, the image does
+    Some(match crop {
+        Some(crop) => image.crop(crop),
+        None => image,
+    })
+}
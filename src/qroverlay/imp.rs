@@ -0,0 +1,234 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::subclass::prelude::*;
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::codec::{Alignment, EncoderSettings, Payload, Placement};
+use crate::render;
+use crate::stamp::{self, Stamper};
+use crate::MAXIMUM_FPS;
+use crate::MINIMUM_SIZE;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "qrtimestampoverlay",
+        gst::DebugColorFlags::empty(),
+        Some("Draws qrcodes based on current linux timestamp over video frames"),
+    )
+});
+
+const DEFAULT_ALIGNMENT: Alignment = Alignment::TopLeft;
+const DEFAULT_QR_SCALE: f64 = 0.25;
+
+/// Unlike the source, the qrcode only covers a corner of the frame by default
+fn default_stamp() -> stamp::Settings {
+    stamp::Settings {
+        encoder: EncoderSettings {
+            placement: Placement {
+                alignment: DEFAULT_ALIGNMENT,
+                scale: DEFAULT_QR_SCALE,
+                ..Placement::default()
+            },
+            ..EncoderSettings::default()
+        },
+        ..stamp::Settings::default()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    info: Option<gst_video::VideoInfo>,
+    /// Frames stamped since start, encoded as the sequence number
+    n_frames: u64,
+}
+
+pub struct QRTimeStampOverlay {
+    stamper: Stamper,
+    state: Mutex<State>,
+}
+
+impl Default for QRTimeStampOverlay {
+    fn default() -> Self {
+        QRTimeStampOverlay {
+            stamper: Stamper::new(*CAT, default_stamp()),
+            state: Mutex::default(),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for QRTimeStampOverlay {
+    const NAME: &'static str = "GstRsQRTimeStampOverlay";
+    type Type = super::QRTimeStampOverlay;
+    type ParentType = gst_base::BaseTransform;
+}
+
+impl ObjectImpl for QRTimeStampOverlay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> =
+            Lazy::new(|| Stamper::properties(default_stamp()));
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        if !self
+            .stamper
+            .set_property(self.obj().upcast_ref(), value, pspec)
+        {
+            unreachable!(
+                "GObject only dispatches the installed properties, not {}",
+                pspec.name()
+            );
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        self.stamper.property(pspec).unwrap_or_else(|| {
+            unreachable!(
+                "GObject only dispatches the installed properties, not {}",
+                pspec.name()
+            )
+        })
+    }
+}
+
+impl GstObjectImpl for QRTimeStampOverlay {}
+
+impl ElementImpl for QRTimeStampOverlay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "QRCode Timestamp Overlay",
+                "Filter/Effect/Video",
+                "Draws a QRCode based on the current linux timestamp over each frame",
+                "Patrick José Pereira <patrickelectric@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // Cameras and screen captures may have a variable framerate
+            let caps = gst_video::VideoCapsBuilder::default()
+                .format_list(render::FORMATS)
+                .height_range(MINIMUM_SIZE as i32..i32::MAX)
+                .width_range(MINIMUM_SIZE as i32..i32::MAX)
+                .framerate_range(gst::Fraction::new(0, 1)..=gst::Fraction::from(MAXIMUM_FPS))
+                .build();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseTransformImpl for QRTimeStampOverlay {
+    const MODE: gst_base::subclass::BaseTransformMode =
+        gst_base::subclass::BaseTransformMode::AlwaysInPlace;
+    const PASSTHROUGH_ON_SAME_CAPS: bool = false;
+    const TRANSFORM_IP_ON_PASSTHROUGH: bool = false;
+
+    fn set_caps(&self, incaps: &gst::Caps, outcaps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        gst::debug!(CAT, imp = self, "Configuring for caps {incaps}");
+
+        let info = gst_video::VideoInfo::from_caps(incaps).map_err(|_| {
+            gst::loggable_error!(CAT, "Failed to build `VideoInfo` from caps {incaps}")
+        })?;
+
+        self.state.lock().unwrap().info.replace(info);
+
+        self.parent_set_caps(incaps, outcaps)
+    }
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = Default::default();
+
+        gst::debug!(CAT, imp = self, "Started");
+
+        Ok(())
+    }
+
+    fn transform_ip(
+        &self,
+        buffer: &mut gst::BufferRef,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let stamp = self.stamper.settings();
+
+        let (info, sequence) = {
+            let mut state = self.state.lock().unwrap();
+            let Some(info) = state.info.clone() else {
+                gst::element_imp_error!(self, gst::CoreError::Negotiation, ["Have no caps yet"]);
+                return Err(gst::FlowError::NotNegotiated);
+            };
+
+            let sequence = state.n_frames;
+            state.n_frames += 1;
+
+            (info, sequence)
+        };
+
+        let pts = buffer.pts();
+        let mut frame = gst_video::VideoFrameRef::from_buffer_ref_writable(buffer, &info)
+            .map_err(|_| gst::FlowError::Error)?;
+
+        // The time is read as late as possible, right before drawing
+        let Some(current_time) = stamp.time_source.now(self.obj().upcast_ref()) else {
+            gst::element_imp_error!(
+                self,
+                gst::CoreError::Clock,
+                ["No clock available for time source {:?}", stamp.time_source]
+            );
+            return Err(gst::FlowError::Error);
+        };
+
+        let payload = Payload::new(
+            current_time.nseconds(),
+            stamp.precision,
+            sequence,
+            pts.map(gst::ClockTime::nseconds),
+            stamp.stream_id,
+        );
+        self.stamper
+            .draw(
+                self.obj().upcast_ref(),
+                &*self.stamper.encoder(),
+                &mut frame,
+                &payload,
+            )
+            .map_err(|error| {
+                gst::element_imp_error!(
+                    self,
+                    gst::LibraryError::Encode,
+                    ["Failed to encode qrcode: {error}"]
+                );
+                gst::FlowError::Error
+            })?;
+
+        gst::trace!(CAT, imp = self, "Stamped {payload:?}");
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
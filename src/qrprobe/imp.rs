@@ -0,0 +1,173 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::prelude::*;
+use gst_base::subclass::prelude::*;
+
+use once_cell::sync::Lazy;
+
+use crate::analyzer::Analyzer;
+use crate::luma;
+use crate::MAXIMUM_FPS;
+use crate::MINIMUM_SIZE;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "qrtimestampprobe",
+        gst::DebugColorFlags::empty(),
+        Some("Reads qrcodes of passing frames based on current linux timestamp"),
+    )
+});
+
+pub struct QRTimeStampProbe {
+    analyzer: Analyzer,
+}
+
+impl Default for QRTimeStampProbe {
+    fn default() -> Self {
+        QRTimeStampProbe {
+            analyzer: Analyzer::new(*CAT),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for QRTimeStampProbe {
+    const NAME: &'static str = "GstRsQRTimeStampProbe";
+    type Type = super::QRTimeStampProbe;
+    type ParentType = gst_base::BaseTransform;
+}
+
+impl ObjectImpl for QRTimeStampProbe {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(Analyzer::properties);
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        if !self
+            .analyzer
+            .set_property(self.obj().upcast_ref(), value, pspec)
+        {
+            unreachable!(
+                "GObject only dispatches the installed properties, not {}",
+                pspec.name()
+            );
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        self.analyzer.property(pspec).unwrap_or_else(|| {
+            unreachable!(
+                "GObject only dispatches the installed properties, not {}",
+                pspec.name()
+            )
+        })
+    }
+
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            Analyzer::signals(|args| {
+                let element = args[0]
+                    .get::<super::QRTimeStampProbe>()
+                    .expect("signal arg");
+                element.imp().analyzer.reset_stats(element.upcast_ref());
+            })
+        });
+
+        SIGNALS.as_ref()
+    }
+}
+
+impl GstObjectImpl for QRTimeStampProbe {}
+
+impl ElementImpl for QRTimeStampProbe {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "QRCode Timestamp Probe",
+                "Filter/Analyzer/Video",
+                "Measures the latency of frames from qrtimestampsrc and passes them through unmodified",
+                "Patrick José Pereira <patrickelectric@gmail.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // Cameras and screen captures may have a variable framerate
+            let caps = gst_video::VideoCapsBuilder::default()
+                .format_list(luma::FORMATS)
+                .height_range(MINIMUM_SIZE as i32..i32::MAX)
+                .width_range(MINIMUM_SIZE as i32..i32::MAX)
+                .framerate_range(gst::Fraction::new(0, 1)..=gst::Fraction::from(MAXIMUM_FPS))
+                .build();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseTransformImpl for QRTimeStampProbe {
+    const MODE: gst_base::subclass::BaseTransformMode =
+        gst_base::subclass::BaseTransformMode::AlwaysInPlace;
+    const PASSTHROUGH_ON_SAME_CAPS: bool = true;
+    const TRANSFORM_IP_ON_PASSTHROUGH: bool = true;
+
+    fn set_caps(&self, incaps: &gst::Caps, outcaps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        gst::info!(CAT, imp = self, "Caps set: {incaps}");
+
+        let info = gst_video::VideoInfo::from_caps(incaps).map_err(|_| {
+            gst::loggable_error!(CAT, "Failed to build `VideoInfo` from caps {incaps}")
+        })?;
+
+        self.analyzer.set_info(info);
+
+        self.parent_set_caps(incaps, outcaps)
+    }
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        self.analyzer.start(self.obj().upcast_ref())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        self.analyzer.stop(self.obj().upcast_ref())
+    }
+
+    fn transform_ip_passthrough(
+        &self,
+        buffer: &gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let obj = self.obj();
+        self.analyzer
+            .render(obj.upcast_ref(), buffer, &obj.segment())
+    }
+
+    fn sink_event(&self, event: gst::Event) -> bool {
+        if let gst::EventView::Eos(_) = event.view() {
+            self.analyzer.eos(self.obj().upcast_ref());
+        }
+
+        self.parent_sink_event(event)
+    }
+}
@@ -0,0 +1,17 @@
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct QRTimeStampProbe(ObjectSubclass<imp::QRTimeStampProbe>) @extends gst_base::BaseTransform, gst::Element, gst::Object;
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "qrtimestampprobe",
+        gst::Rank::NONE,
+        QRTimeStampProbe::static_type(),
+    )
+}
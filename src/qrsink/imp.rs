@@ -1,15 +1,14 @@
 use gst::glib;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
+use gst_base::prelude::*;
 use gst_base::subclass::prelude::*;
-use gst_video::{VideoFrameExt, VideoFrameRef};
-
-use std::sync::Mutex;
 
 use once_cell::sync::Lazy;
 
+use crate::analyzer::Analyzer;
+use crate::luma;
 use crate::MAXIMUM_FPS;
-use crate::MINIMUM_FPS;
 use crate::MINIMUM_SIZE;
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
@@ -20,14 +19,16 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     )
 });
 
-#[derive(Default)]
-struct State {
-    info: Option<gst_video::VideoInfo>,
+pub struct QRTimeStampSink {
+    analyzer: Analyzer,
 }
 
-#[derive(Default)]
-pub struct QRTimeStampSink {
-    state: Mutex<State>,
+impl Default for QRTimeStampSink {
+    fn default() -> Self {
+        QRTimeStampSink {
+            analyzer: Analyzer::new(*CAT),
+        }
+    }
 }
 
 #[glib::object_subclass]
@@ -38,11 +39,39 @@ impl ObjectSubclass for QRTimeStampSink {
 }
 
 impl ObjectImpl for QRTimeStampSink {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(Analyzer::properties);
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        if !self
+            .analyzer
+            .set_property(self.obj().upcast_ref(), value, pspec)
+        {
+            unreachable!(
+                "GObject only dispatches the installed properties, not {}",
+                pspec.name()
+            );
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        self.analyzer.property(pspec).unwrap_or_else(|| {
+            unreachable!(
+                "GObject only dispatches the installed properties, not {}",
+                pspec.name()
+            )
+        })
+    }
+
     fn signals() -> &'static [glib::subclass::Signal] {
         static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
-            vec![glib::subclass::Signal::builder("on-render")
-                .param_types([gst_video::VideoInfo::static_type(), i64::static_type()])
-                .build()]
+            Analyzer::signals(|args| {
+                let element = args[0].get::<super::QRTimeStampSink>().expect("signal arg");
+                element.imp().analyzer.reset_stats(element.upcast_ref());
+            })
         });
 
         SIGNALS.as_ref()
@@ -67,13 +96,12 @@ impl ElementImpl for QRTimeStampSink {
 
     fn pad_templates() -> &'static [gst::PadTemplate] {
         static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            // Cameras and screen captures may have a variable framerate
             let caps = gst_video::VideoCapsBuilder::default()
-                .format_list([gst_video::VideoFormat::Rgb])
+                .format_list(luma::FORMATS)
                 .height_range(MINIMUM_SIZE as i32..i32::MAX)
                 .width_range(MINIMUM_SIZE as i32..i32::MAX)
-                .framerate_range(
-                    gst::Fraction::from(MINIMUM_FPS)..=gst::Fraction::from(MAXIMUM_FPS),
-                )
+                .framerate_range(gst::Fraction::new(0, 1)..=gst::Fraction::from(MAXIMUM_FPS))
                 .build();
             // The src pad template must be named "src" for basesrc
             // and specific a pad that is always there
@@ -97,9 +125,7 @@ impl ElementImpl for QRTimeStampSink {
 
         match query.view_mut() {
             QueryViewMut::Convert(convert_query) => {
-                let state = self.state.lock().unwrap();
-
-                if let Some(info) = &state.info {
+                if let Some(info) = &self.analyzer.info() {
                     let (src_val, dest_fmt) = convert_query.get();
 
                     if let Some(dest_val) =
@@ -129,75 +155,41 @@ impl BaseSinkImpl for QRTimeStampSink {
             gst::loggable_error!(CAT, "Failed to build `VideoInfo` from caps {caps}")
         })?;
 
-        self.state.lock().unwrap().info.replace(info);
+        self.analyzer.set_info(info);
 
         Ok(())
     }
 
-    fn render(&self, buffer: &gst::Buffer) -> Result<gst::FlowSuccess, gst::FlowError> {
-        // We need to get time asap to avoid adding the time to the decode logic
-        let time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-
-        let state = self.state.lock().unwrap();
-
-        let Some(info) = &state.info else {
-            return Ok(gst::FlowSuccess::Ok);
-        };
-
-        let frame = VideoFrameRef::from_buffer_ref_readable(buffer, info)
-            .map_err(|_| gst::FlowError::Error)?;
-
-        let Ok(data) = frame.plane_data(0) else {
-            return Ok(gst::FlowSuccess::Ok);
-        };
-
-        let Some(rgb) = image::RgbImage::from_vec(frame.width(), frame.height(), data.into())
-        else {
-            gst::error!(
-                CAT,
-                "Problem creating image buffer: {}x{} ({})",
-                frame.width(),
-                frame.height(),
-                data.len()
-            );
-
-            return Err(gst::FlowError::Error);
-        };
-        let gray = image::DynamicImage::ImageRgb8(rgb).into_luma8();
-
-        let mut qrcode_image = rqrr::PreparedImage::prepare(gray);
-
-        let grids = qrcode_image.detect_grids();
-        if grids.is_empty() {
-            gst::debug!(CAT, "No QRCode grids detected");
-
-            return Ok(gst::FlowSuccess::Ok);
-        }
+    fn propose_allocation(
+        &self,
+        query: &mut gst::query::Allocation,
+    ) -> Result<(), gst::LoggableError> {
+        // Frames are read in place, so we can handle arbitrary strides, plane offsets and crops
+        query.add_allocation_meta::<gst_video::VideoMeta>(None);
+        query.add_allocation_meta::<gst_video::VideoCropMeta>(None);
 
-        let (_meta, content) = match grids[0].decode() {
-            Ok(decoded) => decoded,
-            Err(error) => {
-                gst::debug!(CAT, "Failed decoding QRCode grid: {error}");
+        self.parent_propose_allocation(query)
+    }
 
-                return Ok(gst::FlowSuccess::Ok);
-            }
-        };
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        self.analyzer.start(self.obj().upcast_ref())
+    }
 
-        let content = content.parse::<u64>().unwrap();
-        let latency = if time > content {
-            (time - content) as i64
-        } else {
-            0
-        };
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        self.analyzer.stop(self.obj().upcast_ref())
+    }
 
+    fn render(&self, buffer: &gst::Buffer) -> Result<gst::FlowSuccess, gst::FlowError> {
         let obj = self.obj();
-        obj.emit_by_name::<()>("on-render", &[&info, &latency]);
+        self.analyzer
+            .render(obj.upcast_ref(), buffer, &obj.segment())
+    }
 
-        gst::debug!(CAT, imp = self, "Latency: {latency} ms");
+    fn event(&self, event: gst::Event) -> bool {
+        if let gst::EventView::Eos(_) = event.view() {
+            self.analyzer.eos(self.obj().upcast_ref());
+        }
 
-        Ok(gst::FlowSuccess::Ok)
+        self.parent_event(event)
     }
 }
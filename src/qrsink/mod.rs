@@ -4,7 +4,7 @@ use gst::prelude::*;
 mod imp;
 
 glib::wrapper! {
-    pub struct QRTimeStampSink(ObjectSubclass<imp::QRTimeStampSink>) @extends gst_base::PushSrc, gst_base::BaseSrc, gst::Element, gst::Object;
+    pub struct QRTimeStampSink(ObjectSubclass<imp::QRTimeStampSink>) @extends gst_base::BaseSink, gst::Element, gst::Object;
 }
 
 pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
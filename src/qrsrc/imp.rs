@@ -1,20 +1,25 @@
-use glib::bool_error;
 use gst::glib;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
 use gst_base::prelude::*;
-use gst_base::subclass::base_src::CreateSuccess;
 use gst_base::subclass::prelude::*;
+use gst_video::VideoFrameExt;
 
 use std::sync::Mutex;
 
 use once_cell::sync::Lazy;
-use qrc::{qr_code_to, QRCode};
 
+use crate::codec::{Payload, Rect};
+use crate::render::{self, Color, Pixel};
+use crate::stamp::{self, Stamper};
+use crate::timecode;
+use crate::timesource::TimeSource;
 use crate::MAXIMUM_FPS;
 use crate::MINIMUM_FPS;
 use crate::MINIMUM_SIZE;
 
+use super::TimestampMode;
+
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
         "qrtimestampsrc",
@@ -25,12 +30,26 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 
 const DEFAULT_FPS: i32 = 30;
 const DEFAULT_SIZE: u32 = MINIMUM_SIZE;
-
-#[derive(Debug, Clone, Copy)]
+const DEFAULT_TIMESTAMP_MODE: TimestampMode = TimestampMode::Create;
+const DEFAULT_BACKGROUND_COLOR: Color = Color::WHITE;
+const DEFAULT_REFERENCE_TIMESTAMP_META: bool = true;
+const DEFAULT_TIMECODE_META: bool = true;
+const DEFAULT_TIMECODE_OVERLAY: bool = false;
+/// Largest timecode digits are this many times smaller than the qrcode
+const TIMECODE_SCALE_DIVISOR: u32 = 64;
+
+#[derive(Debug, Clone)]
 struct Settings {
     fps: gst::Fraction,
     width: u32,
     height: u32,
+    timestamp_mode: TimestampMode,
+    background_color: Color,
+    reference_timestamp_meta: bool,
+    /// Caps of the reference timestamp meta, `timestamp/x-unix` for the realtime time source if `None`
+    reference_timestamp_caps: Option<gst::Caps>,
+    timecode_meta: bool,
+    timecode_overlay: bool,
 }
 
 impl Default for Settings {
@@ -39,6 +58,12 @@ impl Default for Settings {
             fps: gst::Fraction::from(DEFAULT_FPS),
             width: DEFAULT_SIZE,
             height: DEFAULT_SIZE,
+            timestamp_mode: DEFAULT_TIMESTAMP_MODE,
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            reference_timestamp_meta: DEFAULT_REFERENCE_TIMESTAMP_META,
+            reference_timestamp_caps: None,
+            timecode_meta: DEFAULT_TIMECODE_META,
+            timecode_overlay: DEFAULT_TIMECODE_OVERLAY,
         }
     }
 }
@@ -56,12 +81,34 @@ struct State {
     accum_rtime: gst::ClockTime,
     /// Accumulated frames for previous caps
     accum_frames: u64,
+
+    /// Difference between the time at which the last frame was released
+    /// and the time encoded in it, in nanoseconds
+    presentation_error: i64,
 }
 
 #[derive(Default)]
+struct ClockWait {
+    clock_id: Option<gst::SingleShotClockId>,
+    flushing: bool,
+}
+
 pub struct QRTimeStampSrc {
     settings: Mutex<Settings>,
+    stamper: Stamper,
     state: Mutex<State>,
+    clock_wait: Mutex<ClockWait>,
+}
+
+impl Default for QRTimeStampSrc {
+    fn default() -> Self {
+        QRTimeStampSrc {
+            settings: Mutex::default(),
+            stamper: Stamper::new(*CAT, stamp::Settings::default()),
+            state: Mutex::default(),
+            clock_wait: Mutex::default(),
+        }
+    }
 }
 
 #[glib::object_subclass]
@@ -84,6 +131,180 @@ impl ObjectImpl for QRTimeStampSrc {
         obj.set_do_timestamp(false);
     }
 
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            let mut properties = vec![
+                glib::ParamSpecEnum::builder_with_default("timestamp-mode", DEFAULT_TIMESTAMP_MODE)
+                    .nick("Timestamp Mode")
+                    .blurb("Which instant the timestamp encoded in each frame refers to")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("background-color")
+                    .nick("Background Color")
+                    .blurb("Color of the frame around the qrcode, big-endian ARGB")
+                    .default_value(DEFAULT_BACKGROUND_COLOR.to_argb())
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("reference-timestamp-meta")
+                    .nick("Reference Timestamp Meta")
+                    .blurb("Attach a GstReferenceTimestampMeta with the encoded time to every buffer")
+                    .default_value(DEFAULT_REFERENCE_TIMESTAMP_META)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Caps>("reference-timestamp-caps")
+                    .nick("Reference Timestamp Caps")
+                    .blurb("Caps of the reference timestamp meta describing the clock of time-source, e.g. timestamp/x-ptp,domain=0, if unset only the realtime time source gets a meta, with timestamp/x-unix caps")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("timecode-meta")
+                    .nick("Timecode Meta")
+                    .blurb("Attach a GstVideoTimeCodeMeta with the SMPTE timecode of the encoded time at the negotiated framerate to every buffer, drop-frame for 29.97 and 59.94 fps")
+                    .default_value(DEFAULT_TIMECODE_META)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("timecode-overlay")
+                    .nick("Timecode Overlay")
+                    .blurb("Draw the digits of the SMPTE timecode next to the qrcode, if there is room for them")
+                    .default_value(DEFAULT_TIMECODE_OVERLAY)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecInt64::builder("presentation-error")
+                    .nick("Presentation Error")
+                    .blurb("Difference in nanoseconds between the time at which the last frame was released and the time encoded in it (presentation mode only)")
+                    .read_only()
+                    .build(),
+            ];
+            properties.extend(Stamper::properties(stamp::Settings::default()));
+
+            properties
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "timestamp-mode" => {
+                let mut settings = self.settings.lock().unwrap();
+                let timestamp_mode = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing timestamp-mode from {:?} to {:?}",
+                    settings.timestamp_mode,
+                    timestamp_mode,
+                );
+                settings.timestamp_mode = timestamp_mode;
+            }
+            "background-color" => {
+                let mut settings = self.settings.lock().unwrap();
+                let background_color =
+                    Color::from_argb(value.get().expect("type checked upstream"));
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing background-color from {:?} to {:?}",
+                    settings.background_color,
+                    background_color,
+                );
+                settings.background_color = background_color;
+            }
+            "reference-timestamp-meta" => {
+                let mut settings = self.settings.lock().unwrap();
+                let reference_timestamp_meta = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing reference-timestamp-meta from {} to {}",
+                    settings.reference_timestamp_meta,
+                    reference_timestamp_meta,
+                );
+                settings.reference_timestamp_meta = reference_timestamp_meta;
+            }
+            "reference-timestamp-caps" => {
+                let mut settings = self.settings.lock().unwrap();
+                let reference_timestamp_caps = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing reference-timestamp-caps from {:?} to {:?}",
+                    settings.reference_timestamp_caps,
+                    reference_timestamp_caps,
+                );
+                settings.reference_timestamp_caps = reference_timestamp_caps;
+            }
+            "timecode-meta" => {
+                let mut settings = self.settings.lock().unwrap();
+                let timecode_meta = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing timecode-meta from {} to {}",
+                    settings.timecode_meta,
+                    timecode_meta,
+                );
+                settings.timecode_meta = timecode_meta;
+            }
+            "timecode-overlay" => {
+                let mut settings = self.settings.lock().unwrap();
+                let timecode_overlay = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing timecode-overlay from {} to {}",
+                    settings.timecode_overlay,
+                    timecode_overlay,
+                );
+                settings.timecode_overlay = timecode_overlay;
+            }
+            _ => {
+                if !self
+                    .stamper
+                    .set_property(self.obj().upcast_ref(), value, pspec)
+                {
+                    unreachable!(
+                        "GObject only dispatches the installed properties, not {}",
+                        pspec.name()
+                    );
+                }
+            }
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "timestamp-mode" => self.settings.lock().unwrap().timestamp_mode.to_value(),
+            "background-color" => self
+                .settings
+                .lock()
+                .unwrap()
+                .background_color
+                .to_argb()
+                .to_value(),
+            "reference-timestamp-meta" => self
+                .settings
+                .lock()
+                .unwrap()
+                .reference_timestamp_meta
+                .to_value(),
+            "reference-timestamp-caps" => self
+                .settings
+                .lock()
+                .unwrap()
+                .reference_timestamp_caps
+                .to_value(),
+            "timecode-meta" => self.settings.lock().unwrap().timecode_meta.to_value(),
+            "timecode-overlay" => self.settings.lock().unwrap().timecode_overlay.to_value(),
+            "presentation-error" => self.state.lock().unwrap().presentation_error.to_value(),
+            _ => self.stamper.property(pspec).unwrap_or_else(|| {
+                unreachable!(
+                    "GObject only dispatches the installed properties, not {}",
+                    pspec.name()
+                )
+            }),
+        }
+    }
+
     fn signals() -> &'static [glib::subclass::Signal] {
         static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
             vec![glib::subclass::Signal::builder("on-create")
@@ -114,7 +335,7 @@ impl ElementImpl for QRTimeStampSrc {
     fn pad_templates() -> &'static [gst::PadTemplate] {
         static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
             let caps = gst_video::VideoCapsBuilder::default()
-                .format_list([gst_video::VideoFormat::Rgb])
+                .format_list(render::FORMATS)
                 .height_range(MINIMUM_SIZE as i32..i32::MAX)
                 .width_range(MINIMUM_SIZE as i32..i32::MAX)
                 .framerate_range(
@@ -171,16 +392,8 @@ impl BaseSrcImpl for QRTimeStampSrc {
 
         gst::debug!(CAT, imp = self, "Configuring for caps {caps}");
 
-        let width = info.width();
-        let height = info.height();
-        if width != height {
-            return Err(gst::LoggableError::new(
-                *CAT,
-                bool_error!("Width ({width}) and height ({height}) should be from the same size"),
-            ));
-        }
-        settings.width = width;
-        settings.height = height;
+        settings.width = info.width();
+        settings.height = info.height();
         settings.fps = info.fps();
 
         state.info.replace(info);
@@ -296,27 +509,100 @@ impl BaseSrcImpl for QRTimeStampSrc {
     fn is_seekable(&self) -> bool {
         false
     }
+
+    fn decide_allocation(
+        &self,
+        query: &mut gst::query::Allocation,
+    ) -> Result<(), gst::LoggableError> {
+        let (Some(caps), _) = query.get_owned() else {
+            return Err(gst::loggable_error!(CAT, "No caps in allocation query"));
+        };
+        let info = gst_video::VideoInfo::from_caps(&caps).map_err(|_| {
+            gst::loggable_error!(CAT, "Failed to build `VideoInfo` from caps {caps}")
+        })?;
+
+        // Prefer the pool proposed by downstream, e.g. from an encoder or appsink
+        let (pool, size, min, max, update) = match query.allocation_pools().first() {
+            Some((pool, size, min, max)) => (
+                pool.clone(),
+                (*size).max(info.size() as u32),
+                *min,
+                *max,
+                true,
+            ),
+            None => (None, info.size() as u32, 0, 0, false),
+        };
+        let video_meta = query
+            .find_allocation_meta::<gst_video::VideoMeta>()
+            .is_some();
+
+        // Downstream pools may be shared or already active, and refuse our configuration
+        let pool = match pool {
+            Some(pool) => match self.configure_pool(&pool, &caps, size, min, max, video_meta) {
+                Ok(()) => pool,
+                Err(err) => {
+                    gst::debug!(
+                        CAT,
+                        imp = self,
+                        "Downstream pool {pool:?} can't be used ({err}), using our own"
+                    );
+                    self.new_pool(&caps, size, min, max, video_meta)?
+                }
+            },
+            None => self.new_pool(&caps, size, min, max, video_meta)?,
+        };
+
+        gst::debug!(
+            CAT,
+            imp = self,
+            "Using pool {pool:?} with buffers of {size} bytes (min {min}, max {max})"
+        );
+
+        if update {
+            query.set_nth_allocation_pool(0, Some(&pool), size, min, max);
+        } else {
+            query.add_allocation_pool(Some(&pool), size, min, max);
+        }
+
+        Ok(())
+    }
+
+    fn unlock(&self) -> Result<(), gst::ErrorMessage> {
+        // Called whenever the source is flushing and create() has to return as soon as
+        // possible, even if it is currently waiting for the presentation time of a frame
+        gst::debug!(CAT, imp = self, "Unlocking");
+        let mut clock_wait = self.clock_wait.lock().unwrap();
+        if let Some(clock_id) = clock_wait.clock_id.take() {
+            clock_id.unschedule();
+        }
+        clock_wait.flushing = true;
+
+        Ok(())
+    }
+
+    fn unlock_stop(&self) -> Result<(), gst::ErrorMessage> {
+        gst::debug!(CAT, imp = self, "Unlock stop");
+        let mut clock_wait = self.clock_wait.lock().unwrap();
+        clock_wait.flushing = false;
+
+        Ok(())
+    }
 }
 
 impl PushSrcImpl for QRTimeStampSrc {
-    fn create(
-        &self,
-        _buffer: Option<&mut gst::BufferRef>,
-    ) -> Result<CreateSuccess, gst::FlowError> {
-        let settings = self.settings.lock().unwrap();
+    // Buffers are allocated from the negotiated pool by the base class, so we only fill them
+    fn fill(&self, buffer: &mut gst::BufferRef) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let stamp = self.stamper.settings();
         let mut state = self.state.lock().unwrap();
 
-        if state.info.is_none() {
+        let Some(info) = state.info.clone() else {
             gst::element_imp_error!(self, gst::CoreError::Negotiation, ["Have no caps yet"]);
             return Err(gst::FlowError::NotNegotiated);
         };
 
-        let mut buffer =
-            gst::Buffer::with_size((settings.width * settings.height * 3) as usize).unwrap();
-        let buffer = buffer.make_mut();
-
         // Time
-        {
+        let (pts, offset) = {
             let pts = state.accum_rtime + state.running_time;
             buffer.set_pts(pts);
             buffer.set_dts(gst::ClockTime::NONE);
@@ -346,36 +632,320 @@ impl PushSrcImpl for QRTimeStampSrc {
             buffer.set_duration(duration);
 
             state.running_time = next_time;
-        }
+
+            (pts, offset)
+        };
+
+        // The state lock can't be held while waiting on the clock
+        drop(state);
+
+        let presentation = match settings.timestamp_mode {
+            TimestampMode::Create => None,
+            TimestampMode::Presentation => {
+                let presentation = self.presentation_time(stamp.time_source, pts);
+                if presentation.is_none() {
+                    gst::warning!(
+                        CAT,
+                        imp = self,
+                        "No clock or base time available, encoding the creation time instead"
+                    );
+                }
+                presentation
+            }
+        };
 
         // Image
-        {
-            let current_time = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default();
+        let (current_time, timestamp, timecode) = {
+            let current_time = match &presentation {
+                Some((_, _, time)) => *time,
+                None => self.now(stamp.time_source)?,
+            };
+
+            let payload = Payload::new(
+                current_time.nseconds(),
+                stamp.precision,
+                offset,
+                Some(pts.nseconds()),
+                stamp.stream_id,
+            );
+
+            let mut frame = gst_video::VideoFrameRef::from_buffer_ref_writable(buffer, &info)
+                .map_err(|_| gst::FlowError::Error)?;
+
+            let background = Pixel::new(settings.background_color, &info);
+            let encoder = self.stamper.encoder();
+            if encoder.keeps_chroma() {
+                // Codes keeping the chroma modulate the background, so it is drawn first
+                let frame_rect = Rect {
+                    x: 0,
+                    y: 0,
+                    width: frame.width(),
+                    height: frame.height(),
+                };
+                render::fill_rect(&mut frame, frame_rect, background);
+            }
+
+            let qr_rect = self
+                .stamper
+                .draw(self.obj().upcast_ref(), &*encoder, &mut frame, &payload)
+                .map_err(|error| {
+                    gst::element_imp_error!(
+                        self,
+                        gst::LibraryError::Encode,
+                        ["Failed to encode qrcode: {error}"]
+                    );
+                    gst::FlowError::Error
+                })?;
+
+            render::fill_outside(&mut frame, qr_rect, background);
+
+            let timecode = timecode::from_timestamp(payload.timestamp, info.fps());
+            if let (true, Some(timecode)) = (settings.timecode_overlay, &timecode) {
+                self.draw_timecode(&mut frame, qr_rect, timecode);
+            }
+
+            (current_time, payload.timestamp, timecode)
+        };
+
+        if let (true, Some(timecode)) = (settings.timecode_meta, &timecode) {
+            gst_video::VideoTimeCodeMeta::add(buffer, timecode);
+        }
+
+        if settings.reference_timestamp_meta {
+            let caps = settings.reference_timestamp_caps.clone().or_else(|| {
+                (stamp.time_source == TimeSource::Realtime)
+                    .then(|| gst::Caps::new_empty_simple("timestamp/x-unix"))
+            });
+
+            match caps {
+                Some(caps) => {
+                    gst::ReferenceTimestampMeta::add(
+                        buffer,
+                        &caps,
+                        gst::ClockTime::from_nseconds(timestamp),
+                        gst::ClockTime::NONE,
+                    );
+                }
+                None => gst::trace!(
+                    CAT,
+                    imp = self,
+                    "No reference timestamp caps for time source {:?}",
+                    stamp.time_source
+                ),
+            }
+        }
+
+        // The frame is rendered ahead of time, so we hold it until its presentation time
+        if let Some((clock, clock_time, _)) = presentation {
+            self.wait_until(&clock, clock_time)?;
+
+            let error = clock_time_diff(self.now(stamp.time_source)?, current_time);
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Frame {pts} released {error} ns after its encoded time"
+            );
+            self.state.lock().unwrap().presentation_error = error;
+        }
+
+        let obj = self.obj();
+        obj.emit_by_name::<()>("on-create", &[&info]);
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+impl QRTimeStampSrc {
+    /// Configures `pool` for buffers of `size` bytes with `caps`. Like the base classes,
+    /// a pool adjusting the configuration is accepted if its own still fits the parameters.
+    fn configure_pool(
+        &self,
+        pool: &gst::BufferPool,
+        caps: &gst::Caps,
+        size: u32,
+        min: u32,
+        max: u32,
+        video_meta: bool,
+    ) -> Result<(), glib::BoolError> {
+        let mut config = pool.config();
+        config.set_params(Some(caps), size, min, max);
+        // With video metas, downstream can handle the strides and offsets chosen by the pool
+        if video_meta && pool.has_option(gst_video::BUFFER_POOL_OPTION_VIDEO_META) {
+            config.add_option(gst_video::BUFFER_POOL_OPTION_VIDEO_META);
+        }
+        if pool.set_config(config).is_ok() {
+            return Ok(());
+        }
+
+        let config = pool.config();
+        config.validate_params(Some(caps), size, min, max)?;
+        // An active pool can't be configured, but it already produces suitable buffers
+        if pool.is_active() {
+            return Ok(());
+        }
+
+        pool.set_config(config)
+    }
+
+    fn new_pool(
+        &self,
+        caps: &gst::Caps,
+        size: u32,
+        min: u32,
+        max: u32,
+        video_meta: bool,
+    ) -> Result<gst::BufferPool, gst::LoggableError> {
+        let pool = gst_video::VideoBufferPool::new().upcast();
+        self.configure_pool(&pool, caps, size, min, max, video_meta)
+            .map_err(|err| gst::loggable_error!(CAT, "Failed to configure pool: {err}"))?;
+
+        Ok(pool)
+    }
+
+    /// Reads the current time from `time_source`
+    fn now(&self, time_source: TimeSource) -> Result<gst::ClockTime, gst::FlowError> {
+        time_source.now(self.obj().upcast_ref()).ok_or_else(|| {
+            gst::element_imp_error!(
+                self,
+                gst::CoreError::Clock,
+                ["No clock available for time source {time_source:?}"]
+            );
+            gst::FlowError::Error
+        })
+    }
+
+    /// Computes the clock time at which a frame with `pts` is scheduled to be pushed
+    /// (base time + running time), and the time it corresponds to in `time_source`
+    fn presentation_time(
+        &self,
+        time_source: TimeSource,
+        pts: gst::ClockTime,
+    ) -> Option<(gst::Clock, gst::ClockTime, gst::ClockTime)> {
+        let obj = self.obj();
+        let clock = obj.clock()?;
+        let base_time = obj.base_time()?;
+        let running_time = obj
+            .segment()
+            .downcast_ref::<gst::ClockTime>()?
+            .to_running_time(pts)?;
+        let clock_time = base_time + running_time;
+
+        let source_clock = time_source.clock(obj.upcast_ref())?;
+        if source_clock == clock {
+            return Some((clock, clock_time, clock_time));
+        }
+
+        // Both readings are taken back to back so the mapping between the clocks is as tight as possible
+        let clock_now = clock.time()?;
+        let source_now = source_clock.time()?;
+
+        let time = if clock_time >= clock_now {
+            source_now + (clock_time - clock_now)
+        } else {
+            source_now.saturating_sub(clock_now - clock_time)
+        };
+
+        Some((clock, clock_time, time))
+    }
 
-            let data = (current_time.as_millis() as u64).to_string();
-            let png = qr_code_to!(data.into(), "png", settings.width);
+    /// Draws the digits of `timecode` next to the qrcode in `qr_rect`, as large as they fit
+    fn draw_timecode(
+        &self,
+        frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+        qr_rect: Rect,
+        timecode: &gst_video::ValidVideoTimeCode,
+    ) {
+        let text = timecode.to_string();
+        let (width, height) = (frame.width(), frame.height());
+        let max_scale = (qr_rect.height / TIMECODE_SCALE_DIVISOR).max(1);
+        let Some((rect, scale)) = (1..=max_scale).rev().find_map(|scale| {
+            let (text_width, text_height) = render::text_size(&text, scale);
+            let rect = beside(qr_rect, text_width, text_height, width, height)?;
+            Some((rect, scale))
+        }) else {
+            gst::trace!(CAT, imp = self, "No room for timecode {text}");
+            return;
+        };
+
+        if let Some(mut image) = render::code_image(frame) {
+            render::draw_text(&mut image, rect.x, rect.y, &text, scale);
+        }
 
-            let mut buffer_map = buffer.map_writable().unwrap();
-            let rgb_channels = buffer_map.chunks_exact_mut(3);
-            let rgba_channels = png.chunks_exact(4);
+        let info = frame.info().clone();
+        render::fill_chroma_and_alpha(frame, rect, Pixel::new(Color::WHITE, &info));
+    }
+
+    /// Blocks until `clock` reaches `clock_time`, or until the element is unlocked
+    fn wait_until(
+        &self,
+        clock: &gst::Clock,
+        clock_time: gst::ClockTime,
+    ) -> Result<(), gst::FlowError> {
+        let clock_id = clock.new_single_shot_id(clock_time);
 
-            // Copy the png (RGBA) into the RGB, omitting the alpha channel
-            rgb_channels
-                .zip(rgba_channels)
-                .for_each(|(dest, src)| dest.copy_from_slice(&src[..3]));
+        {
+            let mut clock_wait = self.clock_wait.lock().unwrap();
+            if clock_wait.flushing {
+                gst::debug!(CAT, imp = self, "Flushing");
+                return Err(gst::FlowError::Flushing);
+            }
+            clock_wait.clock_id = Some(clock_id.clone());
         }
 
-        if let Some(info) = &state.info {
-            let obj = self.obj();
-            obj.emit_by_name::<()>("on-create", &[info]);
+        gst::log!(CAT, imp = self, "Waiting until {clock_time}");
+        let (res, jitter) = clock_id.wait();
+        gst::log!(
+            CAT,
+            imp = self,
+            "Waited with result {res:?} and jitter {jitter}"
+        );
+
+        let mut clock_wait = self.clock_wait.lock().unwrap();
+        clock_wait.clock_id = None;
+        if res == Err(gst::ClockError::Unscheduled) {
+            gst::debug!(CAT, imp = self, "Flushing");
+            return Err(gst::FlowError::Flushing);
         }
 
-        Ok(CreateSuccess::NewBuffer(buffer.to_owned()))
+        Ok(())
     }
 }
 
+/// Area of `width`x`height` pixels next to `rect` and inside a frame of
+/// `frame_width`x`frame_height`: right of it, below it, left of it or above it, in this order
+fn beside(
+    rect: Rect,
+    width: u32,
+    height: u32,
+    frame_width: u32,
+    frame_height: u32,
+) -> Option<Rect> {
+    let right = Rect {
+        x: rect.x + rect.width,
+        y: rect.y,
+        width,
+        height,
+    };
+    let below = Rect {
+        x: rect.x,
+        y: rect.y + rect.height,
+        width,
+        height,
+    };
+    let left = rect.x.checked_sub(width).map(|x| Rect { x, ..right });
+    let above = rect.y.checked_sub(height).map(|y| Rect { y, ..below });
+
+    [Some(right), Some(below), left, above]
+        .into_iter()
+        .flatten()
+        .find(|area| area.x + area.width <= frame_width && area.y + area.height <= frame_height)
+}
+
+/// Signed difference `a - b` in nanoseconds
+fn clock_time_diff(a: gst::ClockTime, b: gst::ClockTime) -> i64 {
+    a.nseconds() as i64 - b.nseconds() as i64
+}
+
 /// Rounds an integer value up to the next multiple of 4.
 /// reference: https://gstreamer.freedesktop.org/documentation/gstreamer/gstutils.html?gi-language=c#GST_ROUND_UP_4
 fn gst_round_up_4(num: u32) -> u32 {
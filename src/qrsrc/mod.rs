@@ -7,6 +7,21 @@ glib::wrapper! {
     pub struct QRTimeStampSrc(ObjectSubclass<imp::QRTimeStampSrc>) @extends gst_base::PushSrc, gst_base::BaseSrc, gst::Element, gst::Object;
 }
 
+/// Which instant the timestamp encoded in each frame refers to
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstQRTimeStampSrcTimestampMode")]
+pub enum TimestampMode {
+    #[default]
+    #[enum_value(name = "Create: Time at which the frame is created", nick = "create")]
+    Create = 0,
+    #[enum_value(
+        name = "Presentation: Time at which the frame is scheduled to be pushed (base time + running time)",
+        nick = "presentation"
+    )]
+    Presentation = 1,
+}
+
 pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     gst::Element::register(
         Some(plugin),
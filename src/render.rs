@@ -0,0 +1,277 @@
+use gst_video::prelude::*;
+
+use std::ops::Range;
+
+use crate::codec::{ImageMut, Rect};
+use crate::luma;
+
+/// Raw video formats that can be rendered into, all of them with 8 bits per component
+pub const FORMATS: [gst_video::VideoFormat; 7] = [
+    gst_video::VideoFormat::Rgb,
+    gst_video::VideoFormat::I420,
+    gst_video::VideoFormat::Nv12,
+    gst_video::VideoFormat::Yuy2,
+    gst_video::VideoFormat::Gray8,
+    gst_video::VideoFormat::Rgba,
+    gst_video::VideoFormat::Bgrx,
+];
+
+/// Non-linear (gamma encoded) sRGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    /// Creates a color from a big-endian ARGB value, the alpha channel is ignored
+    pub fn from_argb(argb: u32) -> Self {
+        let [_a, r, g, b] = argb.to_be_bytes();
+        Color { r, g, b }
+    }
+
+    pub fn to_argb(self) -> u32 {
+        u32::from_be_bytes([u8::MAX, self.r, self.g, self.b])
+    }
+}
+
+/// A color converted to the component values of a specific video format,
+/// in the same order as the format components (Y, U, V, A or R, G, B, A)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pixel([u8; 4]);
+
+impl Pixel {
+    /// Converts `color` following the colorimetry and range of `info`
+    pub fn new(color: Color, info: &gst_video::VideoInfo) -> Self {
+        if info.is_rgb() {
+            return Pixel([color.r, color.g, color.b, u8::MAX]);
+        }
+
+        let colorimetry = info.colorimetry();
+        // RGB and unknown matrices have no coefficients, BT.601 is what GStreamer assumes then
+        let (kr, kb) = colorimetry.matrix().kr_kb().unwrap_or((0.299, 0.114));
+
+        let r = color.r as f64 / 255.0;
+        let g = color.g as f64 / 255.0;
+        let b = color.b as f64 / 255.0;
+
+        let y = kr * r + (1.0 - kr - kb) * g + kb * b;
+        let pb = (b - y) / (2.0 * (1.0 - kb));
+        let pr = (r - y) / (2.0 * (1.0 - kr));
+
+        // Gray formats are always full range
+        let full_range =
+            info.is_gray() || colorimetry.range() == gst_video::VideoColorRange::Range0_255;
+        let (y, u, v) = if full_range {
+            (y * 255.0, 128.0 + pb * 255.0, 128.0 + pr * 255.0)
+        } else {
+            (16.0 + y * 219.0, 128.0 + pb * 224.0, 128.0 + pr * 224.0)
+        };
+
+        let clamp = |value: f64| value.round().clamp(0.0, 255.0) as u8;
+
+        Pixel([clamp(y), clamp(u), clamp(v), u8::MAX])
+    }
+}
+
+/// Fills `rect` with `pixel`, clipping it to the frame.
+/// Subsampled components are written for every sample that the rectangle touches.
+pub fn fill_rect(
+    frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    rect: Rect,
+    pixel: Pixel,
+) {
+    let n_components = frame.format_info().n_components() as usize;
+    fill_components(frame, rect, pixel, 0..n_components);
+}
+
+/// Fills the chroma and alpha components of `rect` with those of `pixel`, which codes drawn
+/// through [`code_image`] leave untouched
+pub fn fill_chroma_and_alpha(
+    frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    rect: Rect,
+    pixel: Pixel,
+) {
+    let finfo = frame.format_info();
+    let first = if finfo.is_rgb() { 3 } else { 1 };
+    fill_components(frame, rect, pixel, first..finfo.n_components() as usize);
+}
+
+fn fill_components(
+    frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    rect: Rect,
+    pixel: Pixel,
+    components: Range<usize>,
+) {
+    let x_end = rect.x.saturating_add(rect.width).min(frame.width());
+    let y_end = rect.y.saturating_add(rect.height).min(frame.height());
+    if rect.x >= x_end || rect.y >= y_end {
+        return;
+    }
+
+    let finfo = frame.format_info();
+    for component in components {
+        let plane = finfo.plane()[component];
+        let poffset = finfo.poffset()[component] as usize;
+        let pstride = finfo.pixel_stride()[component] as usize;
+        let w_sub = finfo.w_sub()[component];
+        let h_sub = finfo.h_sub()[component];
+        let stride = frame.plane_stride()[plane as usize] as usize;
+        let value = pixel.0[component];
+
+        // Round the start down and the end up, so partially covered samples are included
+        let x0 = (rect.x >> w_sub) as usize;
+        let x1 = (x_end as usize + (1 << w_sub) - 1) >> w_sub;
+        let y0 = (rect.y >> h_sub) as usize;
+        let y1 = (y_end as usize + (1 << h_sub) - 1) >> h_sub;
+
+        let Ok(data) = frame.plane_data_mut(plane) else {
+            continue;
+        };
+
+        for row in data.chunks_mut(stride).take(y1).skip(y0) {
+            let row = &mut row[x0 * pstride..];
+            if pstride == 1 {
+                row[..x1 - x0].fill(value);
+            } else {
+                row.chunks_mut(pstride)
+                    .take(x1 - x0)
+                    .for_each(|sample| sample[poffset] = value);
+            }
+        }
+    }
+}
+
+/// Image over the luma, or the R, G and B components, of `frame`, to draw codes into.
+/// Its black and white levels follow the colorimetry and range of the frame.
+/// Returns `None` if the frame format is not supported.
+pub fn code_image<'a>(
+    frame: &'a mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+) -> Option<ImageMut<'a>> {
+    if !FORMATS.contains(&frame.format()) {
+        return None;
+    }
+
+    let info = frame.info().clone();
+    let black = Pixel::new(Color::BLACK, &info).0[0];
+    let white = Pixel::new(Color::WHITE, &info).0[0];
+
+    let finfo = frame.format_info();
+    let layout = luma::layout(&finfo);
+    let plane = finfo.plane()[0];
+    let stride = frame.plane_stride()[plane as usize] as usize;
+    let (width, height) = (frame.width(), frame.height());
+    let data = frame.plane_data_mut(plane).ok()?;
+
+    Some(ImageMut::new(data, width, height, stride, layout)?.with_levels(black, white))
+}
+
+/// Fills everything in the frame except `rect` with `pixel`
+pub fn fill_outside(
+    frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    rect: Rect,
+    pixel: Pixel,
+) {
+    let (width, height) = (frame.width(), frame.height());
+    let rect_bottom = rect.y + rect.height;
+    let rect_right = rect.x + rect.width;
+
+    let above = Rect {
+        x: 0,
+        y: 0,
+        width,
+        height: rect.y,
+    };
+    let below = Rect {
+        x: 0,
+        y: rect_bottom,
+        width,
+        height: height.saturating_sub(rect_bottom),
+    };
+    let left = Rect {
+        x: 0,
+        y: rect.y,
+        width: rect.x,
+        height: rect.height,
+    };
+    let right = Rect {
+        x: rect_right,
+        y: rect.y,
+        width: width.saturating_sub(rect_right),
+        height: rect.height,
+    };
+
+    for area in [above, below, left, right] {
+        fill_rect(frame, area, pixel);
+    }
+}
+
+/// Glyph of a timecode character, 3x5 pixels with one row of 3 bits per entry
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => return None,
+    })
+}
+
+/// Size of `text` drawn by [`draw_text`] with glyph pixels of `scale`x`scale`
+pub fn text_size(text: &str, scale: u32) -> (u32, u32) {
+    // Every glyph is followed by a spacing column, with a margin of one glyph pixel around
+    let chars = text.chars().count() as u32;
+    ((chars * 4 + 1) * scale, 7 * scale)
+}
+
+/// Draws `text` in black over a white box at (`x`, `y`), with glyph pixels of `scale`x`scale`.
+/// Characters other than digits, `:`, `;` and `.` are left blank.
+pub fn draw_text(image: &mut ImageMut, x: u32, y: u32, text: &str, scale: u32) {
+    let (width, height) = text_size(text, scale);
+    let (black, white) = (image.black(), image.white());
+    image.fill_rect(
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        },
+        white,
+    );
+
+    for (i, glyph) in text.chars().map(glyph).enumerate() {
+        let Some(glyph) = glyph else {
+            continue;
+        };
+
+        let glyph_x = x + (1 + 4 * i as u32) * scale;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in (0..3).filter(|column| bits & (0b100 >> column) != 0) {
+                let pixel = Rect {
+                    x: glyph_x + column * scale,
+                    y: y + (1 + row as u32) * scale,
+                    width: scale,
+                    height: scale,
+                };
+                image.fill_rect(pixel, black);
+            }
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use gst::glib;
+use gst::prelude::*;
+use gst_video::VideoFrameExt;
+
+use std::sync::{Arc, Mutex};
+
+use crate::codec::{self, EncoderSettings, Payload, Precision, Rect, TimestampEncoder};
+use crate::render::{self, Color, Pixel};
+use crate::timesource::TimeSource;
+
+const MAXIMUM_QR_VERSION: u32 = 40;
+const MAXIMUM_WATERMARK_STRENGTH: u32 = 64;
+const MAXIMUM_MODULE_SIZE: u32 = 256;
+const MAXIMUM_QUIET_ZONE: u32 = 64;
+
+/// How the timestamp qrcode is encoded and drawn into frames
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    pub time_source: TimeSource,
+    pub stream_id: u32,
+    pub precision: Precision,
+    /// Name of the backend drawing the code
+    pub code_type: &'static str,
+    pub encoder: EncoderSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            time_source: TimeSource::Realtime,
+            stream_id: 0,
+            precision: Precision::Microseconds,
+            code_type: "qr",
+            encoder: EncoderSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Encoder of the `code_type` backend, following these settings
+    fn encoder(&self) -> Arc<dyn TimestampEncoder> {
+        let (_, backend) =
+            codec::backend(self.code_type).expect("code-type only accepts registered backends");
+
+        backend.encoder(&self.encoder)
+    }
+}
+
+/// Timestamp qrcode settings shared by the elements that draw them, with their properties.
+/// The element forwards the properties it doesn't handle itself to it.
+pub struct Stamper {
+    cat: gst::DebugCategory,
+    settings: Mutex<Settings>,
+    /// Built from the settings when they change, rather than for every frame
+    encoder: Mutex<Arc<dyn TimestampEncoder>>,
+    /// Last warning of the encoder, which is only logged when it changes
+    warning: Mutex<Option<String>>,
+}
+
+impl Stamper {
+    /// Creates a stamper logging to the debug category of the element, starting from `defaults`
+    pub fn new(cat: gst::DebugCategory, defaults: Settings) -> Self {
+        Stamper {
+            cat,
+            settings: Mutex::new(defaults),
+            encoder: Mutex::new(defaults.encoder()),
+            warning: Mutex::default(),
+        }
+    }
+
+    pub fn settings(&self) -> Settings {
+        *self.settings.lock().unwrap()
+    }
+
+    /// Encoder following the current settings
+    pub fn encoder(&self) -> Arc<dyn TimestampEncoder> {
+        self.encoder.lock().unwrap().clone()
+    }
+
+    /// Encodes `payload` with `encoder` and draws it into `frame`, returning the area of the code.
+    /// The watermark covers the whole frame and keeps its content visible, the strip spans the
+    /// frame width in the rows the qrcode would occupy.
+    pub fn draw(
+        &self,
+        element: &gst::Element,
+        encoder: &dyn TimestampEncoder,
+        frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+        payload: &Payload,
+    ) -> Result<Rect, Box<dyn std::error::Error + Send + Sync>> {
+        let drawn = {
+            let mut image = render::code_image(frame).ok_or("Unsupported video format")?;
+            encoder.encode(&mut image, payload)?
+        };
+
+        if !encoder.keeps_chroma() {
+            let info = frame.info().clone();
+            render::fill_chroma_and_alpha(frame, drawn.rect, Pixel::new(Color::WHITE, &info));
+        }
+
+        let mut warning = self.warning.lock().unwrap();
+        if drawn.warning != *warning {
+            if let Some(warning) = &drawn.warning {
+                gst::warning!(self.cat, obj = element, "{warning}");
+            }
+            *warning = drawn.warning;
+        }
+
+        Ok(drawn.rect)
+    }
+
+    /// Properties of the settings, `defaults` must be the ones given to [`Stamper::new`]
+    pub fn properties(defaults: Settings) -> Vec<glib::ParamSpec> {
+        vec![
+            glib::ParamSpecEnum::builder_with_default("time-source", defaults.time_source)
+                .nick("Time Source")
+                .blurb("Clock used to read the time encoded in each frame")
+                .mutable_ready()
+                .build(),
+            glib::ParamSpecUInt::builder("stream-id")
+                .nick("Stream ID")
+                .blurb("Identifier of this stream, encoded in each frame")
+                .default_value(defaults.stream_id)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecEnum::builder_with_default("precision", defaults.precision)
+                .nick("Precision")
+                .blurb("Unit of the timestamp encoded in each frame")
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecString::builder("code-type")
+                .nick("Code Type")
+                .blurb("Kind of code carrying the timestamp: qr, watermark, strip or a backend registered by the application. The watermark covers the whole frame and the strip spans its width, both ignore the qrcode robustness")
+                .default_value(Some(defaults.code_type))
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt::builder("watermark-strength")
+                .nick("Watermark Strength")
+                .blurb("Luma change of the watermark in 8 bits levels, stronger watermarks are more visible but survive more compression")
+                .minimum(1)
+                .maximum(MAXIMUM_WATERMARK_STRENGTH)
+                .default_value(defaults.encoder.watermark_strength)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecInt::builder("x")
+                .nick("X")
+                .blurb("Horizontal position of the qrcode left edge, -1 to follow the alignment")
+                .minimum(-1)
+                .default_value(defaults.encoder.placement.x)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecInt::builder("y")
+                .nick("Y")
+                .blurb("Vertical position of the qrcode top edge, -1 to follow the alignment")
+                .minimum(-1)
+                .default_value(defaults.encoder.placement.y)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecEnum::builder_with_default("alignment", defaults.encoder.placement.alignment)
+                .nick("Alignment")
+                .blurb("Placement of the qrcode in the frame when x or y are -1")
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt::builder("qr-size")
+                .nick("QRCode Size")
+                .blurb("Side of the qrcode in pixels, 0 to follow qr-scale")
+                .default_value(defaults.encoder.placement.size)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecDouble::builder("qr-scale")
+                .nick("QRCode Scale")
+                .blurb("Side of the qrcode as a fraction of the smallest frame dimension, used when qr-size is 0")
+                .minimum(0.0)
+                .maximum(1.0)
+                .default_value(defaults.encoder.placement.scale)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecEnum::builder_with_default("ecc-level", defaults.encoder.ecc_level)
+                .nick("ECC Level")
+                .blurb("Error correction level of the qrcode")
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt::builder("module-size")
+                .nick("Module Size")
+                .blurb("Minimum size of each qrcode module in pixels, the qrcode grows beyond qr-size or qr-scale if needed")
+                .minimum(1)
+                .maximum(MAXIMUM_MODULE_SIZE)
+                .default_value(defaults.encoder.module_size)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt::builder("quiet-zone")
+                .nick("Quiet Zone")
+                .blurb("Width of the light border around the qrcode, in modules")
+                .maximum(MAXIMUM_QUIET_ZONE)
+                .default_value(defaults.encoder.quiet_zone)
+                .mutable_playing()
+                .build(),
+            glib::ParamSpecUInt::builder("qr-version")
+                .nick("QRCode Version")
+                .blurb("Fixed qrcode version (1-40), 0 to use the smallest one that fits the payload")
+                .maximum(MAXIMUM_QR_VERSION)
+                .default_value(defaults.encoder.qr_version)
+                .mutable_playing()
+                .build(),
+        ]
+    }
+
+    /// Sets one of the properties of the stamper, returns `false` if `pspec` is not one of them
+    pub fn set_property(
+        &self,
+        element: &gst::Element,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) -> bool {
+        match pspec.name() {
+            "time-source" => {
+                let mut settings = self.settings.lock().unwrap();
+                let time_source = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing time-source from {:?} to {:?}",
+                    settings.time_source,
+                    time_source,
+                );
+                settings.time_source = time_source;
+            }
+            "stream-id" => {
+                let mut settings = self.settings.lock().unwrap();
+                let stream_id = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing stream-id from {} to {}",
+                    settings.stream_id,
+                    stream_id,
+                );
+                settings.stream_id = stream_id;
+            }
+            "precision" => {
+                let mut settings = self.settings.lock().unwrap();
+                let precision = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing precision from {:?} to {:?}",
+                    settings.precision,
+                    precision,
+                );
+                settings.precision = precision;
+            }
+            "code-type" => {
+                let mut settings = self.settings.lock().unwrap();
+                let name = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+                let Some((code_type, _)) = name.as_deref().and_then(codec::backend) else {
+                    gst::warning!(
+                        self.cat,
+                        obj = element,
+                        "Unknown code-type {name:?}, keeping {}",
+                        settings.code_type,
+                    );
+                    return true;
+                };
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing code-type from {} to {}",
+                    settings.code_type,
+                    code_type,
+                );
+                settings.code_type = code_type;
+            }
+            "watermark-strength" => {
+                let mut settings = self.settings.lock().unwrap();
+                let watermark_strength = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing watermark-strength from {} to {}",
+                    settings.encoder.watermark_strength,
+                    watermark_strength,
+                );
+                settings.encoder.watermark_strength = watermark_strength;
+            }
+            "x" => {
+                let mut settings = self.settings.lock().unwrap();
+                let x = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing x from {} to {}",
+                    settings.encoder.placement.x,
+                    x,
+                );
+                settings.encoder.placement.x = x;
+            }
+            "y" => {
+                let mut settings = self.settings.lock().unwrap();
+                let y = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing y from {} to {}",
+                    settings.encoder.placement.y,
+                    y,
+                );
+                settings.encoder.placement.y = y;
+            }
+            "alignment" => {
+                let mut settings = self.settings.lock().unwrap();
+                let alignment = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing alignment from {:?} to {:?}",
+                    settings.encoder.placement.alignment,
+                    alignment,
+                );
+                settings.encoder.placement.alignment = alignment;
+            }
+            "qr-size" => {
+                let mut settings = self.settings.lock().unwrap();
+                let size = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing qr-size from {} to {}",
+                    settings.encoder.placement.size,
+                    size,
+                );
+                settings.encoder.placement.size = size;
+            }
+            "qr-scale" => {
+                let mut settings = self.settings.lock().unwrap();
+                let scale = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing qr-scale from {} to {}",
+                    settings.encoder.placement.scale,
+                    scale,
+                );
+                settings.encoder.placement.scale = scale;
+            }
+            "ecc-level" => {
+                let mut settings = self.settings.lock().unwrap();
+                let ecc_level = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing ecc-level from {:?} to {:?}",
+                    settings.encoder.ecc_level,
+                    ecc_level,
+                );
+                settings.encoder.ecc_level = ecc_level;
+            }
+            "module-size" => {
+                let mut settings = self.settings.lock().unwrap();
+                let module_size = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing module-size from {} to {}",
+                    settings.encoder.module_size,
+                    module_size,
+                );
+                settings.encoder.module_size = module_size;
+            }
+            "quiet-zone" => {
+                let mut settings = self.settings.lock().unwrap();
+                let quiet_zone = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing quiet-zone from {} to {}",
+                    settings.encoder.quiet_zone,
+                    quiet_zone,
+                );
+                settings.encoder.quiet_zone = quiet_zone;
+            }
+            "qr-version" => {
+                let mut settings = self.settings.lock().unwrap();
+                let qr_version = value.get().expect("type checked upstream");
+                gst::info!(
+                    self.cat,
+                    obj = element,
+                    "Changing qr-version from {} to {}",
+                    settings.encoder.qr_version,
+                    qr_version,
+                );
+                settings.encoder.qr_version = qr_version;
+            }
+            _ => return false,
+        }
+
+        // Every other property changes how the code is drawn
+        if !matches!(pspec.name(), "time-source" | "stream-id" | "precision") {
+            let settings = self.settings.lock().unwrap();
+            *self.encoder.lock().unwrap() = settings.encoder();
+        }
+
+        true
+    }
+
+    /// Value of one of the properties of the stamper, `None` if `pspec` is not one of them
+    pub fn property(&self, pspec: &glib::ParamSpec) -> Option<glib::Value> {
+        Some(match pspec.name() {
+            "time-source" => self.settings.lock().unwrap().time_source.to_value(),
+            "stream-id" => self.settings.lock().unwrap().stream_id.to_value(),
+            "precision" => self.settings.lock().unwrap().precision.to_value(),
+            "code-type" => self.settings.lock().unwrap().code_type.to_value(),
+            "watermark-strength" => self
+                .settings
+                .lock()
+                .unwrap()
+                .encoder
+                .watermark_strength
+                .to_value(),
+            "x" => self.settings.lock().unwrap().encoder.placement.x.to_value(),
+            "y" => self.settings.lock().unwrap().encoder.placement.y.to_value(),
+            "alignment" => self
+                .settings
+                .lock()
+                .unwrap()
+                .encoder
+                .placement
+                .alignment
+                .to_value(),
+            "qr-size" => self
+                .settings
+                .lock()
+                .unwrap()
+                .encoder
+                .placement
+                .size
+                .to_value(),
+            "qr-scale" => self
+                .settings
+                .lock()
+                .unwrap()
+                .encoder
+                .placement
+                .scale
+                .to_value(),
+            "ecc-level" => self.settings.lock().unwrap().encoder.ecc_level.to_value(),
+            "module-size" => self.settings.lock().unwrap().encoder.module_size.to_value(),
+            "quiet-zone" => self.settings.lock().unwrap().encoder.quiet_zone.to_value(),
+            "qr-version" => self.settings.lock().unwrap().encoder.qr_version.to_value(),
+            _ => return None,
+        })
+    }
+}
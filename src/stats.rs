@@ -0,0 +1,161 @@
+/// Sub-buckets per power of two, bounding the relative error of percentiles to 1/128
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+/// Magnitudes from 2^40 ns (about 18 minutes) on share the last bucket
+const MAX_BITS: u32 = 40;
+const BUCKETS: usize = ((MAX_BITS - SUB_BUCKET_BITS + 1) as u64 * SUB_BUCKETS) as usize;
+
+/// Percentiles reported by [`LatencyStats::write`], with their field names
+const PERCENTILES: [(&str, f64); 4] = [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("p99.9", 0.999)];
+
+/// Log-linear histogram of magnitudes, with bounded memory and relative error
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: Vec<u64>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            buckets: vec![0; BUCKETS],
+        }
+    }
+}
+
+impl Histogram {
+    fn index(magnitude: u64) -> usize {
+        if magnitude < SUB_BUCKETS {
+            return magnitude as usize;
+        }
+
+        let exponent = 63 - magnitude.leading_zeros();
+        if exponent >= MAX_BITS {
+            return BUCKETS - 1;
+        }
+
+        let shift = exponent - SUB_BUCKET_BITS;
+        let sub_bucket = (magnitude >> shift) - SUB_BUCKETS;
+        ((shift + 1) as u64 * SUB_BUCKETS + sub_bucket) as usize
+    }
+
+    /// Middle of the range of magnitudes that fall in the bucket at `index`
+    fn value(index: usize) -> u64 {
+        let group = index as u64 / SUB_BUCKETS;
+        let sub_bucket = index as u64 % SUB_BUCKETS;
+        if group == 0 {
+            return sub_bucket;
+        }
+
+        let shift = group - 1;
+        ((SUB_BUCKETS + sub_bucket) << shift) + ((1 << shift) >> 1)
+    }
+
+    fn push(&mut self, magnitude: u64) {
+        self.buckets[Self::index(magnitude)] += 1;
+    }
+}
+
+/// Running statistics of signed values in nanoseconds, e.g. latencies
+#[derive(Debug, Clone, Default)]
+pub struct LatencyStats {
+    count: u64,
+    min: i64,
+    max: i64,
+    /// Welford's running mean and sum of squared differences from it
+    mean: f64,
+    m2: f64,
+    negative: Histogram,
+    positive: Histogram,
+}
+
+impl LatencyStats {
+    pub fn push(&mut self, value: i64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+
+        self.count += 1;
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value as f64 - self.mean);
+
+        if value < 0 {
+            self.negative.push(value.unsigned_abs());
+        } else {
+            self.positive.push(value as u64);
+        }
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<i64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Sample standard deviation
+    pub fn std_dev(&self) -> Option<f64> {
+        (self.count > 1).then(|| (self.m2 / (self.count - 1) as f64).sqrt())
+    }
+
+    /// Value below which a `fraction` of the values fall, estimated from the histogram
+    pub fn percentile(&self, fraction: f64) -> Option<i64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((fraction * self.count as f64).ceil() as u64).clamp(1, self.count);
+
+        // Negative values are ordered from the largest magnitude to the smallest
+        let negatives = self
+            .negative
+            .buckets
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, count)| (-(Histogram::value(index) as i64), *count));
+        let positives = self
+            .positive
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(index, count)| (Histogram::value(index) as i64, *count));
+
+        let mut seen = 0;
+        for (value, count) in negatives.chain(positives) {
+            seen += count;
+            if seen >= rank {
+                return Some(value.clamp(self.min, self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+
+    /// Writes the statistics as fields of `structure`, fields without values are left unset
+    pub fn write(&self, structure: &mut gst::StructureRef) {
+        structure.set("count", self.count);
+        if let (Some(min), Some(max), Some(mean)) = (self.min(), self.max(), self.mean()) {
+            structure.set("min", min);
+            structure.set("max", max);
+            structure.set("mean", mean);
+        }
+        if let Some(std_dev) = self.std_dev() {
+            structure.set("stddev", std_dev);
+        }
+        for (name, fraction) in PERCENTILES {
+            if let Some(value) = self.percentile(fraction) {
+                structure.set(name, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+const DAY: gst::ClockTime = gst::ClockTime::from_seconds(24 * 60 * 60);
+
+/// SMPTE timecode of the frame encoding `timestamp`, in nanoseconds, with frames counted at `fps`
+/// since the last midnight of the time source. 29.97 and 59.94 fps use drop-frame timecodes.
+/// Returns `None` for framerates without timecodes, like variable ones.
+pub fn from_timestamp(timestamp: u64, fps: gst::Fraction) -> Option<gst_video::ValidVideoTimeCode> {
+    let numer = u64::try_from(fps.numer()).ok().filter(|numer| *numer > 0)?;
+    let denom = u64::try_from(fps.denom()).ok().filter(|denom| *denom > 0)?;
+
+    let flags = if denom == 1001 && (numer == 30000 || numer == 60000) {
+        gst_video::VideoTimeCodeFlags::DROP_FRAME
+    } else {
+        gst_video::VideoTimeCodeFlags::empty()
+    };
+
+    let time_of_day = (timestamp % DAY.nseconds()) as u128;
+    let frames =
+        time_of_day * numer as u128 / (denom as u128 * gst::ClockTime::SECOND.nseconds() as u128);
+
+    // Adding the frames to midnight skips the frame numbers dropped by drop-frame timecodes
+    let mut timecode = gst_video::ValidVideoTimeCode::new(fps, None, flags, 0, 0, 0, 0, 0).ok()?;
+    timecode.add_frames(frames as i64);
+
+    Some(timecode)
+}
@@ -0,0 +1,59 @@
+use gst::glib;
+use gst::prelude::*;
+
+use once_cell::sync::Lazy;
+
+/// Clock used by the elements to read the current time
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstQRTimeStampTimeSource")]
+pub enum TimeSource {
+    #[default]
+    #[enum_value(
+        name = "Realtime: CLOCK_REALTIME, time since the unix epoch",
+        nick = "realtime"
+    )]
+    Realtime = 0,
+    #[enum_value(
+        name = "Monotonic: CLOCK_MONOTONIC, not affected by wall-clock steps",
+        nick = "monotonic"
+    )]
+    Monotonic = 1,
+    #[enum_value(name = "TAI: CLOCK_TAI, international atomic time", nick = "tai")]
+    Tai = 2,
+    #[enum_value(
+        name = "Clock: The element's clock, e.g. a GstNetClientClock or GstPtpClock selected for the pipeline",
+        nick = "clock"
+    )]
+    Clock = 3,
+}
+
+static REALTIME_CLOCK: Lazy<gst::Clock> = Lazy::new(|| system_clock(gst::ClockType::Realtime));
+static MONOTONIC_CLOCK: Lazy<gst::Clock> = Lazy::new(|| system_clock(gst::ClockType::Monotonic));
+static TAI_CLOCK: Lazy<gst::Clock> = Lazy::new(|| system_clock(gst::ClockType::Tai));
+
+/// Creates a private system clock, so the global one used by pipelines is left untouched
+fn system_clock(clock_type: gst::ClockType) -> gst::Clock {
+    glib::Object::builder::<gst::SystemClock>()
+        .property("clock-type", clock_type)
+        .build()
+        .upcast()
+}
+
+impl TimeSource {
+    /// Returns the clock backing this time source for `element`,
+    /// `None` if it should use the element's clock and it has none yet
+    pub fn clock(self, element: &gst::Element) -> Option<gst::Clock> {
+        match self {
+            TimeSource::Realtime => Some(REALTIME_CLOCK.clone()),
+            TimeSource::Monotonic => Some(MONOTONIC_CLOCK.clone()),
+            TimeSource::Tai => Some(TAI_CLOCK.clone()),
+            TimeSource::Clock => element.clock(),
+        }
+    }
+
+    /// Current time according to this time source
+    pub fn now(self, element: &gst::Element) -> Option<gst::ClockTime> {
+        self.clock(element)?.time()
+    }
+}
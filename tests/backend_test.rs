@@ -0,0 +1,68 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Every code type of the source must be decoded by the sink set to the same code type
+fn main() {
+    prepare();
+
+    let buffers = 10;
+    for code_type in gstqrtimestamp::codec::names() {
+        let pipeline = gst::parse::launch(&format!(
+            concat!(
+                "qrtimestampsrc code-type={code_type} num-buffers={buffers}",
+                " ! video/x-raw,format=I420,width=640,height=480",
+                " ! qrtimestampsink name=sink code-type={code_type} sync=false",
+            ),
+            code_type = code_type,
+            buffers = buffers,
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let rendered = Arc::new(Mutex::new(0));
+        let rendered_cloned = rendered.clone();
+        let qrtimestampsink = pipeline.by_name("sink").unwrap();
+        qrtimestampsink.connect("on-render", false, move |_values| {
+            *rendered_cloned.lock().unwrap() += 1;
+
+            None
+        });
+
+        // Start
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // Wait for EOS
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    panic!(
+                        "Error from {:?}: {} ({:?})",
+                        err.src().map(|s| s.path_string()),
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        // Cleanup
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        assert_eq!(*rendered.lock().unwrap(), buffers, "{code_type}");
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Timestamps from the monotonic clock read against the realtime one look like huge latencies,
+/// which must be reported as unsynchronised clocks with an estimate of their offset
+fn main() {
+    prepare();
+
+    let buffers = 120;
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc time-source=monotonic num-buffers={buffers}",
+            " ! qrtimestampsink name=sink time-source=realtime sync=false",
+        ),
+        buffers = buffers,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS, gathering the clock warnings
+    let mut offsets = Vec::new();
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            MessageView::Warning(warning) => {
+                let details = warning.details().unwrap();
+                assert_eq!(details.name(), "qrtimestamp-clock-offset");
+                offsets.push(details.get::<i64>("clock-offset").unwrap());
+            }
+            _ => (),
+        }
+    }
+
+    let stats = pipeline
+        .by_name("sink")
+        .unwrap()
+        .property::<gst::Structure>("stats");
+    dbg!(&stats, &offsets);
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    // The clocks stay unsynchronised, so the warning is posted once
+    assert_eq!(offsets.len(), 1);
+    // The realtime clock is ahead of the monotonic one by years, not by a plausible latency
+    assert!(offsets[0] > 10 * gst::ClockTime::SECOND.nseconds() as i64);
+
+    assert!(stats.get::<bool>("clock-unsynchronised").unwrap());
+    // Both estimates are the smallest latency of a window, which only varies with the real latency
+    let offset = stats.get::<i64>("clock-offset").unwrap();
+    assert!((offset - offsets[0]).abs() < gst::ClockTime::SECOND.nseconds() as i64);
+}
@@ -0,0 +1,89 @@
+use gstqrtimestamp::codec::{
+    self, Image, ImageMut, Layout, Payload, Placement, Precision, QrEncoder, StripEncoder,
+    TimestampEncoder, WatermarkEncoder,
+};
+
+#[test]
+/// Payloads drawn into plain luma and RGB buffers must be detected back, without GStreamer
+fn main() {
+    let payload = Payload::new(
+        1_700_000_000_123_456_789,
+        Precision::Microseconds,
+        42,
+        Some(1_000_000),
+        7,
+    );
+
+    let encoders: [(&str, Box<dyn TimestampEncoder>); 3] = [
+        (
+            "qr",
+            Box::new(QrEncoder {
+                placement: Placement::default(),
+                ecc_level: codec::EccLevel::M,
+                module_size: 1,
+                quiet_zone: 4,
+                version: 0,
+            }),
+        ),
+        ("watermark", Box::new(WatermarkEncoder { strength: 4 })),
+        (
+            "strip",
+            Box::new(StripEncoder {
+                placement: Placement::default(),
+            }),
+        ),
+    ];
+
+    let (width, height) = (640, 480);
+    // Rows are padded, like in most video buffers
+    for (layout, stride) in [(Layout::GRAY8, 656), (Layout::RGB, 1936)] {
+        for (code_type, encoder) in &encoders {
+            let mut data = vec![128; stride * height as usize];
+
+            let mut image = ImageMut::new(&mut data, width, height, stride, layout).unwrap();
+            encoder.encode(&mut image, &payload).unwrap();
+
+            let image = Image::new(&data, width, height, stride, layout).unwrap();
+            let (decoded, found) = codec::detect(&image, codec::AUTO).unwrap();
+            dbg!(&layout, &code_type, &decoded);
+
+            assert_eq!(found, *code_type);
+            assert_eq!(decoded.payload.timestamp, payload.timestamp);
+            assert_eq!(decoded.payload.precision, payload.precision);
+            assert_eq!(decoded.payload.sequence, payload.sequence);
+            assert_eq!(decoded.payload.stream_id, payload.stream_id);
+        }
+    }
+
+    // Modules that can't fit in the image are shrunk, with a warning instead of an overflow
+    for (module_size, quiet_zone) in [(20, 4), (u32::MAX, u32::MAX)] {
+        let mut data = vec![128; width as usize * height as usize];
+        let mut image =
+            ImageMut::new(&mut data, width, height, width as usize, Layout::GRAY8).unwrap();
+        let drawn = QrEncoder {
+            placement: Placement::default(),
+            ecc_level: codec::EccLevel::M,
+            module_size,
+            quiet_zone,
+            version: 0,
+        }
+        .encode(&mut image, &payload)
+        .unwrap();
+
+        assert_eq!(drawn.rect.width, height);
+        assert!(drawn.warning.is_some(), "{module_size} {quiet_zone}");
+    }
+
+    // Strides shorter than a row, and buffers shorter than the image, are refused
+    let mut data = vec![128; 1936 * height as usize];
+    for (layout, stride) in [
+        (Layout::GRAY8, 0),
+        (Layout::GRAY8, width as usize - 1),
+        (Layout::RGB, width as usize * 2),
+    ] {
+        assert!(Image::new(&data, width, height, stride, layout).is_none());
+        assert!(ImageMut::new(&mut data, width, height, stride, layout).is_none());
+    }
+    assert!(Image::new(&data[..656 * 100], width, height, 656, Layout::GRAY8).is_none());
+    assert!(Image::new(&data, width, height, width as usize, Layout::GRAY8).is_some());
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use gstqrtimestamp::codec::{
+    self, Alignment, Backend, EncoderSettings, Placement, StripDecoder, StripEncoder,
+    TimestampDecoder, TimestampEncoder,
+};
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+/// Strips always drawn at the bottom of the frame
+struct BottomStrip;
+
+impl Backend for BottomStrip {
+    fn encoder(&self, settings: &EncoderSettings) -> Arc<dyn TimestampEncoder> {
+        Arc::new(StripEncoder {
+            placement: Placement {
+                alignment: Alignment::Bottom,
+                x: -1,
+                y: -1,
+                ..settings.placement
+            },
+        })
+    }
+
+    fn decoder(&self) -> &dyn TimestampDecoder {
+        &StripDecoder
+    }
+}
+
+#[test]
+/// Backends registered by the application must be selected by the code-type property
+/// of every element, while unknown code types are ignored
+fn main() {
+    prepare();
+
+    codec::register("bottom-strip", &BottomStrip).unwrap();
+
+    let qrtimestampsink = gst::ElementFactory::make("qrtimestampsink")
+        .build()
+        .unwrap();
+    qrtimestampsink.set_property("code-type", "unknown");
+    assert_eq!(qrtimestampsink.property::<String>("code-type"), "auto");
+
+    let buffers = 10;
+    for source in [
+        "qrtimestampsrc code-type=bottom-strip num-buffers={buffers} ! video/x-raw,format=I420,width=640,height=480",
+        "videotestsrc num-buffers={buffers} ! video/x-raw,format=RGB,width=640,height=480,framerate=30/1 ! qrtimestampoverlay code-type=bottom-strip",
+    ] {
+        let source = source.replace("{buffers}", &buffers.to_string());
+        let pipeline = gst::parse::launch(&format!(
+            "{source} ! qrtimestampsink name=sink code-type=bottom-strip sync=false"
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let rendered = Arc::new(Mutex::new(0));
+        let rendered_cloned = rendered.clone();
+        let qrtimestampsink = pipeline.by_name("sink").unwrap();
+        qrtimestampsink.connect("on-render", false, move |_values| {
+            *rendered_cloned.lock().unwrap() += 1;
+
+            None
+        });
+
+        // Start
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // Wait for EOS
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    panic!(
+                        "Error from {:?}: {} ({:?})",
+                        err.src().map(|s| s.path_string()),
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let stats = qrtimestampsink.property::<gst::Structure>("stats");
+        dbg!(&source, &stats);
+
+        // Cleanup
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        assert_eq!(*rendered.lock().unwrap(), buffers, "{source}");
+        assert_eq!(stats.get::<String>("code-type").unwrap(), "bottom-strip");
+    }
+}
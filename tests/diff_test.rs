@@ -1,3 +1,5 @@
+#![cfg(feature = "gst")]
+
 use gst::prelude::*;
 use std::sync::{Arc, Mutex};
 
@@ -47,6 +49,7 @@ fn main() {
         let _info = values[1]
             .get::<gst_video::VideoInfo>()
             .expect("Invalid argument");
+        // Latency in nanoseconds
         let diff = values[2].get::<i64>().expect("Invalid argument");
 
         latencies_cloned.lock().unwrap().push(diff);
@@ -95,8 +98,10 @@ fn main() {
     //      1. We are skipping the first frame as it will always have a high value, possibly from the negotiation
     //      2. We are skipping the last frames because they are the ones that were retained in the queue,
     //      thus as there is no more frames coming in, they are released faster, which lowers their latency
-    let latencies = &latencies.iter().map(|i| *i as f64).collect::<Vec<_>>()
-        [1..=latencies.len() - queue_buffers];
+    let latencies = &latencies
+        .iter()
+        .map(|i| *i as f64 / 1_000_000.0)
+        .collect::<Vec<_>>()[1..=latencies.len() - queue_buffers];
     dbg!(&latencies);
 
     let jitters = &latencies
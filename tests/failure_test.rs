@@ -0,0 +1,156 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+const SIZE: u32 = 320;
+const MODULE: u32 = 8;
+
+/// GRAY8 frame with the qrcode of `content` in the middle, if any.
+/// A damaged qrcode has a block of inverted modules, more than its error correction can fix.
+fn frame(content: Option<&str>, damaged: bool) -> gst::Buffer {
+    let mut data = vec![255; (SIZE * SIZE) as usize];
+
+    if let Some(content) = content {
+        let code =
+            qrcode::QrCode::with_error_correction_level(content, qrcode::EcLevel::L).unwrap();
+        let modules = code.width() as u32;
+        let offset = (SIZE - modules * MODULE) / 2;
+        for y in 0..modules {
+            for x in 0..modules {
+                let inverted = damaged && (9..16).contains(&x) && (9..16).contains(&y);
+                if (code[(x as usize, y as usize)] == qrcode::Color::Dark) == inverted {
+                    continue;
+                }
+
+                for row in 0..MODULE {
+                    let start = ((offset + y * MODULE + row) * SIZE + offset + x * MODULE) as usize;
+                    data[start..start + MODULE as usize].fill(0);
+                }
+            }
+        }
+    }
+
+    gst::Buffer::from_mut_slice(data)
+}
+
+#[test]
+/// Foreign, malformed, damaged and future qrcodes must be classified and counted,
+/// and reported through on-decode-failed, without stopping the sink
+fn main() {
+    prepare();
+
+    let pipeline = gst::parse::launch(&format!(
+        "appsrc name=src format=time caps=video/x-raw,format=GRAY8,width={SIZE},height={SIZE},framerate=30/1 ! qrtimestampsink name=sink code-type=qr sync=false"
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let failures_cloned = failures.clone();
+    let sink = pipeline.by_name("sink").unwrap();
+    sink.connect("on-decode-failed", false, move |values| {
+        let (_, failure) = glib::EnumValue::from_value(&values[2]).unwrap();
+        let content = values[3].get::<Option<String>>().unwrap();
+        failures_cloned
+            .lock()
+            .unwrap()
+            .push((failure.nick().to_string(), content));
+
+        None
+    });
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_micros();
+    let ours = format!("QRTS:2:{}:0:-:0:6", now - 10_000);
+    let future = format!("QRTS:2:{}:1:-:0:6", now + 3_600_000_000);
+    let frames = [
+        frame(None, false),
+        frame(Some("https://example.com"), false),
+        frame(Some("QRTS:2:abc:1:-:0:6"), false),
+        frame(Some(&ours), true),
+        frame(Some(&future), false),
+        frame(Some(&ours), false),
+    ];
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    let src = pipeline.by_name("src").unwrap();
+    for (i, mut buffer) in frames.into_iter().enumerate() {
+        buffer
+            .get_mut()
+            .unwrap()
+            .set_pts(gst::ClockTime::from_mseconds(i as u64 * 33));
+        src.emit_by_name::<gst::FlowReturn>("push-buffer", &[&buffer])
+            .into_result()
+            .unwrap();
+    }
+    src.emit_by_name::<gst::FlowReturn>("end-of-stream", &[])
+        .into_result()
+        .unwrap();
+
+    // Wait for EOS
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+
+    let stats = sink.property::<gst::Structure>("stats");
+    dbg!(&stats, &failures);
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    assert_eq!(
+        *failures.lock().unwrap(),
+        [
+            ("no-grid".to_string(), None),
+            (
+                "foreign-magic".to_string(),
+                Some("https://example.com".to_string())
+            ),
+            (
+                "unparseable".to_string(),
+                Some("QRTS:2:abc:1:-:0:6".to_string())
+            ),
+            ("grid-decode".to_string(), None),
+            ("future-timestamp".to_string(), None),
+        ]
+    );
+
+    assert_eq!(stats.get::<u64>("frames").unwrap(), 6);
+    assert_eq!(stats.get::<u64>("decoded").unwrap(), 1);
+    for counter in [
+        "no-grid",
+        "foreign-magic",
+        "unparseable",
+        "grid-decode",
+        "future-timestamp",
+    ] {
+        assert_eq!(stats.get::<u64>(counter).unwrap(), 1, "{counter}");
+    }
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+/// Runs the source into the sink with the given format, returning how many frames were measured
+fn run(format: &str, width: u32, height: u32, buffers: usize) -> usize {
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc name=src num-buffers={buffers}",
+            " ! video/x-raw,format={format},width={width},height={height},framerate=30/1",
+            " ! qrtimestampsink name=sink",
+        ),
+        buffers = buffers,
+        format = format,
+        width = width,
+        height = height,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    let rendered = Arc::new(Mutex::new(0));
+    let rendered_cloned = rendered.clone();
+    let qrtimestampsink = pipeline.by_name("sink").unwrap();
+    qrtimestampsink.connect("on-render", false, move |_values| {
+        *rendered_cloned.lock().unwrap() += 1;
+
+        None
+    });
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            MessageView::Latency(_latency) => {
+                pipeline.recalculate_latency().unwrap();
+            }
+            _ => (),
+        }
+    }
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let rendered = *rendered.lock().unwrap();
+    rendered
+}
+
+#[test]
+/// Every format supported by both elements must be decodable, including widths with padded rows
+/// and non-square resolutions
+fn main() {
+    prepare();
+
+    let buffers = 5;
+    for format in ["RGB", "I420", "NV12", "YUY2", "GRAY8", "BGRx"] {
+        for (width, height) in [(100, 100), (101, 101), (320, 180)] {
+            let rendered = run(format, width, height, buffers);
+            dbg!(&format, &width, &height, &rendered);
+
+            assert_eq!(rendered, buffers, "{format} {width}x{height}");
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "gst")]
+
 use gst::prelude::*;
 use gstqrtimestamp::MAXIMUM_FPS;
 use std::sync::{Arc, Mutex};
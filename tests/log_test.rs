@@ -0,0 +1,94 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+/// Runs the source into the sink, logging the measurements to `location`
+fn run(location: &std::path::Path, log_format: &str, buffers: usize) {
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc num-buffers={buffers}",
+            " ! video/x-raw,width=200,height=200,framerate=30/1",
+            " ! qrtimestampsink sync=false location=\"{location}\" log-format={log_format}",
+        ),
+        buffers = buffers,
+        location = location.display(),
+        log_format = log_format,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+}
+
+#[test]
+/// Every decoded frame must be written to the measurement log, in both formats
+fn main() {
+    prepare();
+
+    let buffers = 10;
+    let directory = std::env::temp_dir();
+
+    let location = directory.join(format!("qrtimestamp-log-test-{}.csv", std::process::id()));
+    run(&location, "csv", buffers);
+    let csv = std::fs::read_to_string(&location).unwrap();
+    std::fs::remove_file(&location).unwrap();
+    dbg!(&csv);
+
+    let mut lines = csv.lines();
+    let header = lines.next().unwrap().split(',').collect::<Vec<_>>();
+    assert_eq!(header[0], "receive-time");
+    let rows = lines.collect::<Vec<_>>();
+    assert_eq!(rows.len(), buffers);
+    for (sequence, row) in rows.iter().enumerate() {
+        let values = row.split(',').collect::<Vec<_>>();
+        assert_eq!(values.len(), header.len());
+        let column = |name| values[header.iter().position(|c| *c == name).unwrap()];
+        assert_eq!(column("sequence"), sequence.to_string());
+        assert_eq!(column("width"), "200");
+        assert_eq!(column("height"), "200");
+    }
+
+    let location = directory.join(format!("qrtimestamp-log-test-{}.jsonl", std::process::id()));
+    run(&location, "jsonl", buffers);
+    let jsonl = std::fs::read_to_string(&location).unwrap();
+    std::fs::remove_file(&location).unwrap();
+    dbg!(&jsonl);
+
+    let lines = jsonl.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), buffers);
+    for (sequence, line) in lines.iter().enumerate() {
+        assert!(line.starts_with('{') && line.ends_with('}'));
+        assert!(line.contains(&format!("\"sequence\":{sequence},")));
+        assert!(line.contains("\"width\":200,"));
+    }
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Codes stamped on an existing video must be decoded by the sink like the ones of the source,
+/// including variable framerate videos
+fn main() {
+    prepare();
+
+    let buffers = 20;
+    for (format, framerate) in [
+        ("I420", "30/1"),
+        ("RGB", "30/1"),
+        ("YUY2", "30/1"),
+        ("I420", "0/1"),
+    ] {
+        let pipeline = gst::parse::launch(&format!(
+            concat!(
+                "videotestsrc num-buffers={buffers}",
+                " ! video/x-raw,format={format},width=640,height=480,framerate={framerate}",
+                " ! qrtimestampoverlay",
+                " ! qrtimestampsink name=sink sync=false",
+            ),
+            buffers = buffers,
+            format = format,
+            framerate = framerate,
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let rendered = Arc::new(Mutex::new(0));
+        let rendered_cloned = rendered.clone();
+        let qrtimestampsink = pipeline.by_name("sink").unwrap();
+        qrtimestampsink.connect("on-render", false, move |_values| {
+            *rendered_cloned.lock().unwrap() += 1;
+
+            None
+        });
+
+        // Start
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // Wait for EOS
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    panic!(
+                        "Error from {:?}: {} ({:?})",
+                        err.src().map(|s| s.path_string()),
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let stats = qrtimestampsink.property::<gst::Structure>("stats");
+        dbg!(&format, &framerate, &stats);
+
+        // Cleanup
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        // videotestsrc produces a single still frame at a variable framerate
+        let expected = if framerate == "0/1" { 1 } else { buffers };
+        assert_eq!(*rendered.lock().unwrap(), expected, "{format} {framerate}");
+        assert_eq!(stats.get::<u64>("frames-lost").unwrap(), 0);
+    }
+}
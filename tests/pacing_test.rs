@@ -0,0 +1,87 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Frames dropped in a synchronised pipeline leave a gap of two frame periods, which is a stutter
+fn main() {
+    prepare();
+
+    let buffers = 60;
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc name=src num-buffers={buffers}",
+            " ! video/x-raw,framerate=30/1",
+            " ! qrtimestampsink name=sink",
+        ),
+        buffers = buffers,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Drop one frame out of ten
+    let src_pad = pipeline.by_name("src").unwrap().static_pad("src").unwrap();
+    src_pad.add_probe(gst::PadProbeType::BUFFER, |_pad, info| {
+        match info.buffer() {
+            Some(buffer) if buffer.offset() % 10 == 5 => gst::PadProbeReturn::Drop,
+            _ => gst::PadProbeReturn::Ok,
+        }
+    });
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+
+    let stats = pipeline
+        .by_name("sink")
+        .unwrap()
+        .property::<gst::Structure>("stats");
+    dbg!(&stats);
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let dropped = buffers / 10;
+    let received = (buffers - dropped) as u64;
+
+    assert_eq!(
+        stats.get::<u64>("expected-interval").unwrap(),
+        gst::ClockTime::SECOND.nseconds() / 30
+    );
+    assert!(stats.has_field("jitter"));
+
+    // Every interval is in the histogram, the dropped frames make the ones of two periods
+    let histogram = stats.get::<gst::Array>("interval-histogram").unwrap();
+    let intervals = histogram
+        .iter()
+        .map(|bin| bin.get::<u64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(intervals.iter().sum::<u64>(), received - 1);
+    assert!(intervals[4] >= dropped as u64);
+    assert!(stats.get::<u64>("stutters").unwrap() >= dropped as u64);
+}
@@ -0,0 +1,135 @@
+use gstqrtimestamp::codec::{Payload, PayloadError, Precision, VERSION};
+
+#[test]
+/// Every payload version must keep decoding, so old recordings can still be measured,
+/// and contents that are not ours or are broken must be classified instead of accepted
+fn main() {
+    // Legacy payloads are the timestamp alone, in milliseconds
+    assert_eq!(
+        Payload::decode("1700000000123"),
+        Ok(Payload {
+            version: 0,
+            timestamp: 1_700_000_000_123_000_000,
+            precision: Precision::Milliseconds,
+            sequence: None,
+            pts: None,
+            stream_id: None,
+        })
+    );
+
+    // Version 1 has no precision field and is always in milliseconds
+    assert_eq!(
+        Payload::decode("QRTS:1:1700000000123:5:33333333:3"),
+        Ok(Payload {
+            version: 1,
+            timestamp: 1_700_000_000_123_000_000,
+            precision: Precision::Milliseconds,
+            sequence: Some(5),
+            pts: Some(33_333_333),
+            stream_id: Some(3),
+        })
+    );
+    assert_eq!(
+        Payload::decode("QRTS:1:1700000000123:0:-:0").map(|payload| payload.pts),
+        Ok(None)
+    );
+
+    // Version 2 round-trips at every precision, with and without pts
+    let encoded = Payload::new(
+        1_700_000_000_123_456_789,
+        Precision::Microseconds,
+        42,
+        None,
+        7,
+    )
+    .encode();
+    assert_eq!(encoded, "QRTS:2:1700000000123456:42:-:7:6");
+    for precision in [
+        Precision::Milliseconds,
+        Precision::Microseconds,
+        Precision::Nanoseconds,
+    ] {
+        for pts in [None, Some(1_000_000)] {
+            let payload = Payload::new(1_700_000_000_123_456_789, precision, 42, pts, 7);
+            assert_eq!(payload.version, VERSION);
+            assert_eq!(
+                payload.timestamp,
+                precision.truncate(1_700_000_000_123_456_789)
+            );
+            assert_eq!(Payload::decode(&payload.encode()), Ok(payload));
+
+            let binary = Payload::decode_binary(&payload.encode_binary()).unwrap();
+            assert_eq!(binary.timestamp, payload.timestamp);
+            assert_eq!(binary.precision, payload.precision);
+            assert_eq!(binary.sequence, payload.sequence);
+            assert_eq!(binary.stream_id, payload.stream_id);
+        }
+    }
+
+    // Contents generated by something else
+    for content in [
+        "",
+        "https://example.com",
+        "QRTSX:2:1:1:-:0:6",
+        "12ab",
+        // Numbers too short or too long to be legacy timestamps, like product numbers
+        "12345",
+        "17000000001",
+        "170000000012345",
+        "99999999999999999999",
+    ] {
+        assert_eq!(
+            Payload::decode(content),
+            Err(PayloadError::ForeignMagic),
+            "{content}"
+        );
+    }
+
+    // Versions that don't exist yet, or never existed
+    assert_eq!(
+        Payload::decode("QRTS:3:1:1:-:0:6:extra"),
+        Err(PayloadError::UnsupportedVersion(3))
+    );
+    assert_eq!(
+        Payload::decode("QRTS:0:1"),
+        Err(PayloadError::UnsupportedVersion(0))
+    );
+
+    // Our prefix with fields that can't be parsed
+    for content in [
+        "QRTS",
+        "QRTS:",
+        "QRTS:x:1:1:-:0:6",
+        "QRTS:2:abc:1:-:0:6",
+        "QRTS:2:1:-1:-:0:6",
+        "QRTS:2:1:1:pts:0:6",
+        "QRTS:2:1:1:-:0",
+        "QRTS:2:1:1:-:0:4",
+        "QRTS:1:1:1:-",
+        // Fields after the last one of the version
+        "QRTS:1:1700000000123:5:33333333:3:garbage",
+        "QRTS:2:1:1:-:0:6:extra",
+        "QRTS:2:1:1:-:0:6:",
+        // Timestamps that overflow once converted to nanoseconds
+        "QRTS:2:18446744073709552:1:-:0:3",
+        "99999999999999",
+    ] {
+        assert_eq!(
+            Payload::decode(content),
+            Err(PayloadError::Malformed),
+            "{content}"
+        );
+    }
+
+    // Corrupted binary payloads are caught by their checksum
+    let mut binary = Payload::new(
+        1_700_000_000_123_456_789,
+        Precision::Microseconds,
+        1,
+        None,
+        0,
+    )
+    .encode_binary();
+    binary[5] ^= 0x10;
+    assert_eq!(Payload::decode_binary(&binary), Err(PayloadError::Checksum));
+}
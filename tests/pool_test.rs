@@ -0,0 +1,105 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+/// Active pool producing buffers of `width`x`height` GRAY8 frames
+fn active_pool(width: u32, height: u32) -> gst::BufferPool {
+    let info = gst_video::VideoInfo::builder(gst_video::VideoFormat::Gray8, width, height)
+        .build()
+        .unwrap();
+    let caps = info.to_caps().unwrap();
+
+    let pool = gst::BufferPool::new();
+    let mut config = pool.config();
+    config.set_params(Some(&caps), info.size() as u32, 0, 0);
+    pool.set_config(config).unwrap();
+    pool.set_active(true).unwrap();
+
+    pool
+}
+
+#[test]
+/// Pools proposed by downstream that are already active can't be configured,
+/// the source must use them if they fit the caps and its own pool otherwise
+fn main() {
+    prepare();
+
+    let buffers = 10;
+    for (width, height) in [(320, 240), (160, 120)] {
+        let pipeline = gst::parse::launch(&format!(
+            concat!(
+                "qrtimestampsrc num-buffers={buffers}",
+                " ! video/x-raw,format=GRAY8,width=320,height=240",
+                " ! fakesink name=sink",
+            ),
+            buffers = buffers,
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let pool = active_pool(width, height);
+        let received = Arc::new(Mutex::new(0));
+        let received_cloned = received.clone();
+        let sink_pad = pipeline
+            .by_name("sink")
+            .unwrap()
+            .static_pad("sink")
+            .unwrap();
+        sink_pad.add_probe(
+            gst::PadProbeType::QUERY_DOWNSTREAM | gst::PadProbeType::BUFFER,
+            move |_pad, info| {
+                if info.buffer().is_some() {
+                    *received_cloned.lock().unwrap() += 1;
+                    return gst::PadProbeReturn::Ok;
+                }
+
+                let Some(query) = info.query_mut() else {
+                    return gst::PadProbeReturn::Ok;
+                };
+                let gst::QueryViewMut::Allocation(allocation) = query.view_mut() else {
+                    return gst::PadProbeReturn::Ok;
+                };
+
+                let (_, size, _, _) = pool.config().params().unwrap();
+                allocation.add_allocation_pool(Some(&pool), size, 0, 0);
+
+                gst::PadProbeReturn::Handled
+            },
+        );
+
+        // Start
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // Wait for EOS
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    panic!(
+                        "Error from {:?}: {} ({:?})",
+                        err.src().map(|s| s.path_string()),
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        // Cleanup
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        assert_eq!(*received.lock().unwrap(), buffers, "{width}x{height}");
+    }
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use gstqrtimestamp::codec::{self, Image, Layout, Precision};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+const SIZE: u32 = 320;
+
+fn launch(framerate: &str, buffers: u32) -> gst::Pipeline {
+    gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc name=src timestamp-mode=presentation time-source=clock num-buffers={buffers}",
+            " ! video/x-raw,format=GRAY8,width={size},height={size},framerate={framerate}",
+            " ! fakesink sync=false",
+        ),
+        buffers = buffers,
+        size = SIZE,
+        framerate = framerate,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap()
+}
+
+fn wait_for_eos(pipeline: &gst::Pipeline) {
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+}
+
+#[test]
+/// In presentation mode, frames must be released at their clock time (base time + running time)
+/// and carry it, and shutting down while a frame waits for it must not hang
+fn main() {
+    prepare();
+
+    // The time source is the pipeline clock, so the encoded time is exactly the clock time
+    let buffers = 20;
+    let pipeline = launch("30/1", buffers);
+    let src = pipeline.by_name("src").unwrap();
+
+    let differences = Arc::new(Mutex::new(Vec::new()));
+    let differences_cloned = differences.clone();
+    let src_pad = src.static_pad("src").unwrap();
+    src_pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+        let Some(buffer) = info.buffer() else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        let base_time = pad.parent_element().unwrap().base_time().unwrap();
+        let clock_time = base_time + buffer.pts().unwrap();
+
+        let map = buffer.map_readable().unwrap();
+        let image = Image::new(&map, SIZE, SIZE, SIZE as usize, Layout::GRAY8).unwrap();
+        let (decoded, _) = codec::detect(&image, "qr").unwrap();
+
+        let expected = Precision::Microseconds.truncate(clock_time.nseconds());
+        differences_cloned
+            .lock()
+            .unwrap()
+            .push(decoded.payload.timestamp as i64 - expected as i64);
+
+        gst::PadProbeReturn::Ok
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    wait_for_eos(&pipeline);
+
+    let presentation_error = src.property::<i64>("presentation-error");
+    dbg!(&differences, presentation_error);
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let differences = differences.lock().unwrap();
+    assert_eq!(differences.len(), buffers as usize);
+    assert!(differences.iter().all(|difference| *difference == 0));
+    // The frame is released at its clock time, give or take some scheduling delay
+    assert!(presentation_error.abs() < 50_000_000);
+
+    // At 1 fps the second frame waits about a second for its clock time
+    let pipeline = launch("1/1", 5);
+    let pushed = Arc::new(Mutex::new(0));
+    let pushed_cloned = pushed.clone();
+    let src_pad = pipeline.by_name("src").unwrap().static_pad("src").unwrap();
+    src_pad.add_probe(gst::PadProbeType::BUFFER, move |_pad, _info| {
+        *pushed_cloned.lock().unwrap() += 1;
+
+        gst::PadProbeReturn::Ok
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let start = Instant::now();
+    while *pushed.lock().unwrap() == 0 {
+        assert!(start.elapsed() < Duration::from_secs(5), "No frame pushed");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    std::thread::sleep(Duration::from_millis(200));
+
+    // Unlocking the source interrupts the wait, which returns flushing instead of an error.
+    // The bus is only flushed in the null state, so errors posted while stopping are kept.
+    let stop = Instant::now();
+    pipeline.set_state(gst::State::Ready).unwrap();
+    let stop = stop.elapsed();
+    let error = pipeline
+        .bus()
+        .unwrap()
+        .pop_filtered(&[gst::MessageType::Error]);
+    dbg!(stop, &error);
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    assert!(stop < Duration::from_millis(500));
+    assert_eq!(*pushed.lock().unwrap(), 1);
+    assert!(error.is_none());
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+/// Collects the latencies reported by the `on-render` signal of `element`
+fn gather(element: &gst::Element) -> Arc<Mutex<Vec<i64>>> {
+    let latencies = Arc::new(Mutex::new(Vec::new()));
+    let latencies_cloned = latencies.clone();
+    element.connect("on-render", false, move |values| {
+        let diff = values[2].get::<i64>().expect("Invalid argument");
+        latencies_cloned.lock().unwrap().push(diff);
+
+        None
+    });
+
+    latencies
+}
+
+#[test]
+/// A probe in front of the sink must measure every frame without modifying it,
+/// and the sink, placed after a delay, must measure a higher latency than the probe
+fn main() {
+    prepare();
+
+    let buffers = 30;
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc num-buffers={buffers}",
+            " ! video/x-raw,framerate=30/1",
+            " ! qrtimestampprobe name=probe",
+            " ! queue min-threshold-buffers=5",
+            " ! qrtimestampsink name=sink sync=false",
+        ),
+        buffers = buffers,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    let probe = pipeline.by_name("probe").unwrap();
+    let sink = pipeline.by_name("sink").unwrap();
+    let probe_latencies = gather(&probe);
+    let sink_latencies = gather(&sink);
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS, the probe must also post its summary
+    let mut summaries = Vec::new();
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            MessageView::Element(element)
+                if element.structure().unwrap().name() == "qrtimestamp-summary" =>
+            {
+                summaries.push(element.src().unwrap().name().to_string());
+            }
+            _ => (),
+        }
+    }
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let probe_latencies = probe_latencies.lock().unwrap();
+    let sink_latencies = sink_latencies.lock().unwrap();
+    dbg!(&probe_latencies, &sink_latencies, &summaries);
+
+    assert_eq!(probe_latencies.len(), buffers);
+    assert_eq!(sink_latencies.len(), buffers);
+    for (probe_latency, sink_latency) in probe_latencies.iter().zip(sink_latencies.iter()) {
+        assert!(probe_latency <= sink_latency);
+    }
+
+    summaries.sort();
+    assert_eq!(summaries, ["probe", "sink"]);
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// The sink must find the reference timestamp meta of the source with the decoded timestamp,
+/// and count the frames without one
+fn main() {
+    prepare();
+
+    let buffers = 20u64;
+    for (meta, matched, missing) in [(true, buffers, 0), (false, 0, buffers)] {
+        let pipeline = gst::parse::launch(&format!(
+            "qrtimestampsrc num-buffers={buffers} reference-timestamp-meta={meta} ! qrtimestampsink name=sink reference-timestamp-caps=timestamp/x-unix sync=false"
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        // Start
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // Wait for EOS
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    panic!(
+                        "Error from {:?}: {} ({:?})",
+                        err.src().map(|s| s.path_string()),
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let stats = pipeline
+            .by_name("sink")
+            .unwrap()
+            .property::<gst::Structure>("stats");
+        dbg!(&stats);
+
+        // Cleanup
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        assert_eq!(stats.get::<u64>("decoded").unwrap(), buffers);
+        assert_eq!(
+            stats.get::<u64>("reference-timestamp-matched").unwrap(),
+            matched
+        );
+        assert_eq!(
+            stats.get::<u64>("reference-timestamp-mismatched").unwrap(),
+            0
+        );
+        assert_eq!(
+            stats.get::<u64>("reference-timestamp-missing").unwrap(),
+            missing
+        );
+    }
+}
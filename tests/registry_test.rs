@@ -0,0 +1,124 @@
+use gstqrtimestamp::codec::{
+    self, AlreadyRegistered, Backend, DecodeFailure, Decoded, Drawn, EncoderSettings, Failure,
+    Image, ImageMut, Layout, Payload, Precision, Rect, TimestampDecoder, TimestampEncoder,
+};
+use std::sync::Arc;
+
+/// Side of each block in pixels
+const BLOCK: u32 = 8;
+/// Blocks per row, the 144 bits of the binary payload take 9 rows
+const COLUMNS: u32 = 16;
+
+/// Draws each bit of the binary payload as a black or white block in the top left corner
+struct BlocksEncoder;
+
+impl TimestampEncoder for BlocksEncoder {
+    fn encode(
+        &self,
+        image: &mut ImageMut,
+        payload: &Payload,
+    ) -> Result<Drawn, Box<dyn std::error::Error + Send + Sync>> {
+        let bytes = payload.encode_binary();
+        let bits = bytes.len() as u32 * 8;
+        let (dark, light) = (image.black(), image.white());
+
+        for bit in 0..bits {
+            let value = (bytes[bit as usize / 8] >> (7 - bit % 8)) & 1 == 1;
+            let block = Rect {
+                x: bit % COLUMNS * BLOCK,
+                y: bit / COLUMNS * BLOCK,
+                width: BLOCK,
+                height: BLOCK,
+            };
+            image.fill_rect(block, if value { dark } else { light });
+        }
+
+        Ok(Rect {
+            x: 0,
+            y: 0,
+            width: COLUMNS * BLOCK,
+            height: bits.div_ceil(COLUMNS) * BLOCK,
+        }
+        .into())
+    }
+}
+
+struct BlocksDecoder;
+
+impl TimestampDecoder for BlocksDecoder {
+    fn decode(&self, image: &Image) -> Result<Decoded, Failure> {
+        // Size of the binary payload
+        let mut bytes = [0; 18];
+        for bit in 0..bytes.len() as u32 * 8 {
+            let luma = image.luma(
+                bit % COLUMNS * BLOCK + BLOCK / 2,
+                bit / COLUMNS * BLOCK + BLOCK / 2,
+            );
+            if luma < 128 {
+                bytes[bit as usize / 8] |= 1 << (7 - bit % 8);
+            }
+        }
+
+        let payload = Payload::decode_binary(&bytes).map_err(|_| (DecodeFailure::NoGrid, None))?;
+
+        Ok(Decoded {
+            payload,
+            ecc_level: None,
+            version: None,
+        })
+    }
+}
+
+struct Blocks;
+
+impl Backend for Blocks {
+    fn encoder(&self, _settings: &EncoderSettings) -> Arc<dyn TimestampEncoder> {
+        Arc::new(BlocksEncoder)
+    }
+
+    fn decoder(&self) -> &dyn TimestampDecoder {
+        &BlocksDecoder
+    }
+}
+
+#[test]
+/// Backends implemented outside of the crate must be detected like the built-in ones once
+/// registered, by name or with auto, and names that are already taken must be refused
+fn main() {
+    assert_eq!(codec::names(), ["strip", "qr", "watermark"]);
+    assert!(codec::backend("blocks").is_none());
+
+    codec::register("blocks", &Blocks).unwrap();
+    assert_eq!(codec::names(), ["strip", "qr", "watermark", "blocks"]);
+    for name in ["blocks", "qr", codec::AUTO] {
+        assert_eq!(codec::register(name, &Blocks), Err(AlreadyRegistered(name)));
+    }
+
+    let payload = Payload::new(
+        1_700_000_000_123_456_789,
+        Precision::Microseconds,
+        42,
+        None,
+        7,
+    );
+    let (width, height) = (320, 240);
+    let mut data = vec![128; (width * height) as usize];
+
+    let (name, backend) = codec::backend("blocks").unwrap();
+    assert_eq!(name, "blocks");
+    let encoder = backend.encoder(&EncoderSettings::default());
+    let mut image = ImageMut::new(&mut data, width, height, width as usize, Layout::GRAY8).unwrap();
+    encoder.encode(&mut image, &payload).unwrap();
+
+    let image = Image::new(&data, width, height, width as usize, Layout::GRAY8).unwrap();
+    for code_type in ["blocks", codec::AUTO] {
+        let (decoded, found) = codec::detect(&image, code_type).unwrap();
+        assert_eq!(found, "blocks");
+        assert_eq!(decoded.payload.timestamp, payload.timestamp);
+        assert_eq!(decoded.payload.sequence, payload.sequence);
+    }
+    assert_eq!(
+        codec::detect(&image, "qr").map(|(_, found)| found),
+        Err((DecodeFailure::NoGrid, None))
+    );
+}
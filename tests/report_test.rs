@@ -0,0 +1,84 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Reports must be posted periodically on the bus, followed by a summary of the whole run at EOS
+fn main() {
+    prepare();
+
+    let buffers = 60;
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc num-buffers={buffers}",
+            " ! video/x-raw,framerate=30/1",
+            " ! qrtimestampsink report-interval={interval}",
+        ),
+        buffers = buffers,
+        interval = gst::ClockTime::from_mseconds(500).nseconds(),
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS, gathering the messages
+    let mut reports = Vec::new();
+    let mut summary = None;
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            MessageView::Element(element) => {
+                let structure = element.structure().unwrap().to_owned();
+                match structure.name().as_str() {
+                    "qrtimestamp-report" => reports.push(structure),
+                    "qrtimestamp-summary" => summary = Some(structure),
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    dbg!(&reports, &summary);
+
+    // Two seconds of video make about four reports, the last one is posted at EOS
+    assert!(reports.len() >= 3);
+    let reported_frames: u64 = reports
+        .iter()
+        .map(|report| report.get::<u64>("frames").unwrap())
+        .sum();
+    assert_eq!(reported_frames, buffers);
+
+    for report in &reports {
+        assert_eq!(report.get::<u64>("decode-failures").unwrap(), 0);
+        assert_eq!(report.get::<u64>("frames-lost").unwrap(), 0);
+        assert!(report.has_field("jitter"));
+    }
+
+    let summary = summary.expect("No summary posted at EOS");
+    assert_eq!(summary.get::<u64>("frames").unwrap(), buffers);
+    assert_eq!(summary.get::<u64>("count").unwrap(), buffers);
+}
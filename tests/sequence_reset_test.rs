@@ -0,0 +1,96 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// A frame counted as lost before reset-stats and arriving after it must be counted as reordered,
+/// without the loss counters going below zero
+fn main() {
+    prepare();
+
+    let buffers = 30;
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc name=src num-buffers={buffers}",
+            " ! video/x-raw,framerate=30/1",
+            " ! qrtimestampsink name=sink sync=false",
+        ),
+        buffers = buffers,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Hold back frame 10, then reset the statistics and deliver it before frame 20
+    let sink = pipeline.by_name("sink").unwrap();
+    let sink_pad = sink.static_pad("sink").unwrap();
+    let held = Arc::new(Mutex::new(None));
+    let src_pad = pipeline.by_name("src").unwrap().static_pad("src").unwrap();
+    src_pad.add_probe(gst::PadProbeType::BUFFER, move |_pad, info| {
+        match info.buffer() {
+            Some(buffer) if buffer.offset() == 10 => {
+                held.lock().unwrap().replace(buffer.copy());
+                gst::PadProbeReturn::Drop
+            }
+            Some(buffer) if buffer.offset() == 20 => {
+                sink.emit_by_name::<()>("reset-stats", &[]);
+                let buffer = held.lock().unwrap().take().unwrap();
+                sink_pad.chain(buffer).unwrap();
+                gst::PadProbeReturn::Ok
+            }
+            _ => gst::PadProbeReturn::Ok,
+        }
+    });
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+
+    let stats = pipeline
+        .by_name("sink")
+        .unwrap()
+        .property::<gst::Structure>("stats");
+    dbg!(&stats);
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    assert_eq!(stats.get::<u64>("frames-lost").unwrap(), 0);
+    assert_eq!(stats.get::<u64>("frames-reordered").unwrap(), 1);
+
+    let streams = stats.get::<gst::Array>("streams").unwrap();
+    assert_eq!(streams.len(), 1);
+    let stream = streams[0].get::<gst::Structure>().unwrap();
+    assert_eq!(stream.get::<u64>("lost").unwrap(), 0);
+    assert_eq!(stream.get::<u64>("reordered").unwrap(), 1);
+    assert_eq!(
+        stream.get::<u64>("received").unwrap(),
+        (buffers - 20 + 1) as u64
+    );
+    assert_eq!(stream.get::<f64>("loss-percentage").unwrap(), 0.0);
+}
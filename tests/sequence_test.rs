@@ -0,0 +1,94 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Frames dropped between the source and the sink must be counted as lost, not as latency
+fn main() {
+    prepare();
+
+    let buffers = 50;
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc name=src num-buffers={buffers}",
+            " ! video/x-raw,framerate=30/1",
+            " ! qrtimestampsink name=sink sync=false",
+        ),
+        buffers = buffers,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Drop every fifth frame
+    let src_pad = pipeline.by_name("src").unwrap().static_pad("src").unwrap();
+    src_pad.add_probe(gst::PadProbeType::BUFFER, |_pad, info| {
+        match info.buffer() {
+            Some(buffer) if buffer.offset() % 5 == 4 => gst::PadProbeReturn::Drop,
+            _ => gst::PadProbeReturn::Ok,
+        }
+    });
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS, gathering the loss messages
+    let mut lost_messages = Vec::new();
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            MessageView::Element(element) => {
+                let structure = element.structure().unwrap();
+                if structure.name() == "frames-lost" {
+                    lost_messages.push(structure.to_owned());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let stats = pipeline
+        .by_name("sink")
+        .unwrap()
+        .property::<gst::Structure>("stats");
+    dbg!(&stats, &lost_messages);
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    // The last dropped frame is never followed by another one, so it can't be detected
+    let expected_lost = (buffers / 5 - 1) as u64;
+    assert_eq!(lost_messages.len() as u64, expected_lost);
+    for message in &lost_messages {
+        assert_eq!(message.get::<u64>("lost").unwrap(), 1);
+        assert_eq!(message.get::<u64>("first").unwrap() % 5, 4);
+    }
+
+    assert_eq!(stats.get::<u64>("frames-lost").unwrap(), expected_lost);
+    assert_eq!(stats.get::<u64>("frames-duplicated").unwrap(), 0);
+    assert_eq!(stats.get::<u64>("frames-reordered").unwrap(), 0);
+
+    let streams = stats.get::<gst::Array>("streams").unwrap();
+    assert_eq!(streams.len(), 1);
+    let stream = streams[0].get::<gst::Structure>().unwrap();
+    assert_eq!(stream.get::<u64>("lost").unwrap(), expected_lost);
+    let loss_percentage = stream.get::<f64>("loss-percentage").unwrap();
+    assert!((loss_percentage - expected_lost as f64 * 100.0 / 49.0).abs() < 1e-9);
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// The statistics of the sink must match the latencies reported by `on-render`, after the warm-up
+fn main() {
+    prepare();
+
+    let buffers = 50;
+    let warmup = 5;
+    let pipeline = gst::parse::launch(&format!(
+        concat!(
+            "qrtimestampsrc name=src num-buffers={buffers}",
+            " ! video/x-raw,framerate=100/1",
+            " ! qrtimestampsink name=sink sync=false stats-warmup={warmup}",
+        ),
+        buffers = buffers,
+        warmup = warmup,
+    ))
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Gather all latencies
+    let latencies = Arc::new(Mutex::new(Vec::with_capacity(buffers)));
+    let latencies_cloned = latencies.clone();
+    let qrtimestampsink = pipeline.by_name("sink").unwrap();
+    qrtimestampsink.connect("on-render", false, move |values| {
+        let diff = values[2].get::<i64>().expect("Invalid argument");
+        latencies_cloned.lock().unwrap().push(diff);
+
+        None
+    });
+
+    // Start
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    // Wait for EOS
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                panic!(
+                    "Error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+
+    let stats = qrtimestampsink.property::<gst::Structure>("stats");
+    dbg!(&stats);
+
+    let latencies = latencies.lock().unwrap();
+    let measured = &latencies[warmup..];
+
+    assert_eq!(stats.get::<u64>("frames").unwrap(), buffers as u64);
+    assert_eq!(stats.get::<u64>("count").unwrap(), measured.len() as u64);
+    assert_eq!(
+        stats.get::<i64>("min").unwrap(),
+        *measured.iter().min().unwrap()
+    );
+    assert_eq!(
+        stats.get::<i64>("max").unwrap(),
+        *measured.iter().max().unwrap()
+    );
+    assert_eq!(stats.get::<f64>("decode-success-rate").unwrap(), 1.0);
+
+    // Percentiles are estimated from the histogram, within its relative error
+    let p50 = stats.get::<i64>("p50").unwrap();
+    let p99 = stats.get::<i64>("p99").unwrap();
+    assert!(p50 <= p99);
+    assert!(p99 <= stats.get::<i64>("max").unwrap());
+
+    // Resetting clears the latencies and the counters
+    qrtimestampsink.emit_by_name::<()>("reset-stats", &[]);
+    let stats = qrtimestampsink.property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("count").unwrap(), 0);
+    assert_eq!(stats.get::<u64>("frames").unwrap(), 0);
+    assert!(!stats.has_field("min"));
+
+    // Cleanup
+    pipeline.set_state(gst::State::Null).unwrap();
+}
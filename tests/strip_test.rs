@@ -0,0 +1,70 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Strips must be found by the sink without setting its code type, even after downscaling
+fn main() {
+    prepare();
+
+    let buffers = 20;
+    for source in [
+        "qrtimestampsrc code-type=strip num-buffers={buffers} ! video/x-raw,format=I420,width=1920,height=1080 ! videoscale ! video/x-raw,width=640,height=360",
+        "videotestsrc num-buffers={buffers} ! video/x-raw,format=RGB,width=640,height=480,framerate=30/1 ! qrtimestampoverlay code-type=strip",
+    ] {
+        let source = source.replace("{buffers}", &buffers.to_string());
+        let pipeline = gst::parse::launch(&format!(
+            "{source} ! qrtimestampsink name=sink sync=false"
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let rendered = Arc::new(Mutex::new(0));
+        let rendered_cloned = rendered.clone();
+        let qrtimestampsink = pipeline.by_name("sink").unwrap();
+        qrtimestampsink.connect("on-render", false, move |_values| {
+            *rendered_cloned.lock().unwrap() += 1;
+
+            None
+        });
+
+        // Start
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // Wait for EOS
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    panic!(
+                        "Error from {:?}: {} ({:?})",
+                        err.src().map(|s| s.path_string()),
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let stats = qrtimestampsink.property::<gst::Structure>("stats");
+        dbg!(&source, &stats);
+
+        // Cleanup
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        assert_eq!(*rendered.lock().unwrap(), buffers, "{source}");
+        assert_eq!(stats.get::<String>("code-type").unwrap(), "strip");
+    }
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// The timecode meta of the source must follow the framerate, with drop-frame timecodes for 29.97 fps,
+/// and match the decoded timestamp in the sink even with the digits drawn next to the qrcode
+fn main() {
+    prepare();
+
+    let buffers = 20u64;
+    for ((numer, denom), drop_frame) in [((30000, 1001), true), ((25, 1), false)] {
+        let pipeline = gst::parse::launch(&format!(
+            "qrtimestampsrc name=src num-buffers={buffers} timecode-overlay=true qr-scale=0.5 ! video/x-raw,width=640,height=360,framerate={numer}/{denom} ! qrtimestampsink name=sink sync=false"
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+
+        let timecodes = Arc::new(Mutex::new(Vec::new()));
+        let timecodes_cloned = timecodes.clone();
+        let src_pad = pipeline.by_name("src").unwrap().static_pad("src").unwrap();
+        src_pad.add_probe(gst::PadProbeType::BUFFER, move |_pad, info| {
+            if let Some(buffer) = info.buffer() {
+                let tc = buffer
+                    .meta::<gst_video::VideoTimeCodeMeta>()
+                    .map(|meta| meta.tc());
+                timecodes_cloned.lock().unwrap().push(tc);
+            }
+
+            gst::PadProbeReturn::Ok
+        });
+
+        // Start
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // Wait for EOS
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => {
+                    panic!(
+                        "Error from {:?}: {} ({:?})",
+                        err.src().map(|s| s.path_string()),
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let stats = pipeline
+            .by_name("sink")
+            .unwrap()
+            .property::<gst::Structure>("stats");
+        dbg!(&stats);
+
+        // Cleanup
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        let timecodes = timecodes.lock().unwrap();
+        assert_eq!(timecodes.len() as u64, buffers);
+        for tc in timecodes.iter() {
+            let tc = tc.as_ref().expect("Missing timecode meta");
+            assert_eq!(tc.fps(), gst::Fraction::new(numer, denom));
+            assert_eq!(
+                tc.flags()
+                    .contains(gst_video::VideoTimeCodeFlags::DROP_FRAME),
+                drop_frame
+            );
+        }
+
+        assert_eq!(stats.get::<u64>("decoded").unwrap(), buffers);
+        assert_eq!(stats.get::<u64>("timecode-matched").unwrap(), buffers);
+        assert_eq!(stats.get::<u64>("timecode-mismatched").unwrap(), 0);
+    }
+}
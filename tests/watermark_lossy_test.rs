@@ -0,0 +1,65 @@
+use gstqrtimestamp::codec::{
+    self, Image, ImageMut, Layout, Payload, Precision, TimestampEncoder, WatermarkEncoder,
+};
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+
+/// Pseudo-random numbers, so the test is the same on every run
+fn random(seed: &mut u32) -> u32 {
+    *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+    *seed >> 16
+}
+
+#[test]
+/// The watermark must survive what an encoder does to a picture: noise, coarse quantization,
+/// and high contrast content lying between the cells of a pair, which would outweigh
+/// the other repetitions of its bit if each pair's contribution wasn't bounded
+fn main() {
+    let payload = Payload::new(
+        1_700_000_000_123_456_789,
+        Precision::Microseconds,
+        42,
+        Some(1_000_000),
+        7,
+    );
+
+    // Horizontal gradient, with black and white text-like blocks on the left of the frame
+    let mut seed = 1;
+    let mut data = vec![0; (WIDTH * HEIGHT) as usize];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            data[(y * WIDTH + x) as usize] = (64 + x * 128 / WIDTH) as u8;
+        }
+    }
+    for row in 0..45 {
+        for column in 0..16 {
+            let value = if random(&mut seed) & 1 == 0 { 0 } else { 255 };
+            for y in row * HEIGHT / 45..(row + 1) * HEIGHT / 45 {
+                let start = (y * WIDTH + column * 8) as usize;
+                data[start..start + 8].fill(value);
+            }
+        }
+    }
+
+    let mut image = ImageMut::new(&mut data, WIDTH, HEIGHT, WIDTH as usize, Layout::GRAY8).unwrap();
+    WatermarkEncoder { strength: 4 }
+        .encode(&mut image, &payload)
+        .unwrap();
+
+    // Noise of up to ±12 levels on every pixel, then quantization to 8 levels steps
+    for value in data.iter_mut() {
+        let noise = (random(&mut seed) % 25) as i32 - 12;
+        let noisy = (*value as i32 + noise).clamp(0, 255);
+        *value = (noisy / 8 * 8 + 4).min(255) as u8;
+    }
+
+    let image = Image::new(&data, WIDTH, HEIGHT, WIDTH as usize, Layout::GRAY8).unwrap();
+    let (decoded, found) = codec::detect(&image, "watermark").unwrap();
+    dbg!(&decoded);
+
+    assert_eq!(found, "watermark");
+    assert_eq!(decoded.payload.timestamp, payload.timestamp);
+    assert_eq!(decoded.payload.sequence, payload.sequence);
+    assert_eq!(decoded.payload.stream_id, payload.stream_id);
+}
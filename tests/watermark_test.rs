@@ -0,0 +1,74 @@
+#![cfg(feature = "gst")]
+
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn prepare() {
+    gst::init().unwrap();
+
+    gstqrtimestamp::plugin_register_static().unwrap();
+}
+
+#[test]
+/// Watermarks drawn by the source and by the overlay must be decoded by the sink in watermark mode
+fn main() {
+    prepare();
+
+    let buffers = 20;
+    for source in [
+        "qrtimestampsrc code-type=watermark num-buffers={buffers} ! video/x-raw,format={format},width=640,height=480",
+        "videotestsrc num-buffers={buffers} ! video/x-raw,format={format},width=640,height=480,framerate=30/1 ! qrtimestampoverlay code-type=watermark",
+    ] {
+        for format in ["I420", "RGB"] {
+            let source = source
+                .replace("{buffers}", &buffers.to_string())
+                .replace("{format}", format);
+            let pipeline = gst::parse::launch(&format!(
+                "{source} ! qrtimestampsink name=sink code-type=watermark sync=false"
+            ))
+            .unwrap()
+            .downcast::<gst::Pipeline>()
+            .unwrap();
+
+            let rendered = Arc::new(Mutex::new(0));
+            let rendered_cloned = rendered.clone();
+            let qrtimestampsink = pipeline.by_name("sink").unwrap();
+            qrtimestampsink.connect("on-render", false, move |_values| {
+                *rendered_cloned.lock().unwrap() += 1;
+
+                None
+            });
+
+            // Start
+            pipeline.set_state(gst::State::Playing).unwrap();
+
+            // Wait for EOS
+            let bus = pipeline.bus().unwrap();
+            for msg in bus.iter_timed(gst::ClockTime::NONE) {
+                use gst::MessageView;
+
+                match msg.view() {
+                    MessageView::Eos(..) => break,
+                    MessageView::Error(err) => {
+                        panic!(
+                            "Error from {:?}: {} ({:?})",
+                            err.src().map(|s| s.path_string()),
+                            err.error(),
+                            err.debug()
+                        );
+                    }
+                    _ => (),
+                }
+            }
+
+            let stats = qrtimestampsink.property::<gst::Structure>("stats");
+            dbg!(&source, &stats);
+
+            // Cleanup
+            pipeline.set_state(gst::State::Null).unwrap();
+
+            assert_eq!(*rendered.lock().unwrap(), buffers, "{source}");
+            assert_eq!(stats.get::<u64>("frames-lost").unwrap(), 0);
+        }
+    }
+}